@@ -1,34 +1,253 @@
-use std::fmt;
+use std::{collections::{HashMap, HashSet, VecDeque}, fmt, io::{self, Write}, str::FromStr};
 
 static INPUT: &str = include_str!("../input/dayfour.txt");
 
+// cells stored row by row, so the cell at (x, y) lives at index y * width + x
 #[derive(Clone)]
-struct Grid{ coords: Vec<Coordinate> }
+struct Grid{ coords: Vec<Coordinate>, width: usize, height: usize, rule: Rule, offsets: Vec<(i64, i64)>, kinds: CellKinds }
 
 #[derive(Debug, Clone)]
-struct Coordinate { x: u32, y: u32, has_paper_roll: bool }
+struct Coordinate { x: u32, y: u32, kind: CellKind }
+
+// what a cell of the warehouse holds
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellKind { Floor, Roll, Pallet, Wall, Rack }
+
+impl CellKind {
+    fn name(&self) -> &'static str {
+        match self {
+            CellKind::Floor => "floor",
+            CellKind::Roll => "roll",
+            CellKind::Pallet => "pallet",
+            CellKind::Wall => "wall",
+            CellKind::Rack => "rack",
+        }
+    }
+}
+
+impl FromStr for CellKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [CellKind::Floor, CellKind::Roll, CellKind::Pallet, CellKind::Wall, CellKind::Rack].into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or("Kind must be one of floor, roll, pallet, wall or rack")
+    }
+}
+
+// how one kind of cell is written and how it takes part in peeling
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KindSpec {
+    kind: CellKind,
+    symbol: char,
+    // whether it counts towards the occupied neighbours of the cells around it
+    occupies: bool,
+    removable: bool,
+    // replaces the rule's threshold for this kind
+    threshold: Option<usize>
+}
+
+// <kind>=<symbol> then any of occupies, removable and threshold=<n>, comma separated,
+// such as wall=#,occupies or pallet=P,occupies,removable,threshold=3
+impl FromStr for KindSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(',');
+        let (kind, symbol) = parts.next().unwrap_or("").split_once('=')
+            .ok_or("Kind must start <kind>=<symbol>")?;
+        let mut symbols = symbol.chars();
+        let symbol = match (symbols.next(), symbols.next()) {
+            (Some(symbol), None) => symbol,
+            _ => return Err(format!("Symbol for {} must be a single character", kind)),
+        };
+        let mut spec = KindSpec{ kind: kind.parse()?, symbol, occupies: false, removable: false, threshold: None };
+        for part in parts {
+            match part.split_once('=') {
+                None if part == "occupies" => spec.occupies = true,
+                None if part == "removable" => spec.removable = true,
+                Some(("threshold", threshold)) => spec.threshold = Some(parse_number(threshold, "threshold")?),
+                _ => return Err(format!("Unknown kind setting '{}'", part)),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+// the kinds of cell a grid may hold. the puzzle only has rolls on a floor
+#[derive(Debug, Clone, PartialEq)]
+struct CellKinds { specs: Vec<KindSpec> }
+
+impl Default for CellKinds {
+    fn default() -> Self {
+        CellKinds{ specs: vec![
+            KindSpec{ kind: CellKind::Floor, symbol: '.', occupies: false, removable: false, threshold: None },
+            KindSpec{ kind: CellKind::Roll, symbol: '@', occupies: true, removable: true, threshold: None },
+        ] }
+    }
+}
+
+impl CellKinds {
+    // pallets come away like rolls, while walls and racks block access but never move
+    fn warehouse() -> CellKinds {
+        let mut kinds = CellKinds::default();
+        kinds.specs.extend([
+            KindSpec{ kind: CellKind::Pallet, symbol: 'P', occupies: true, removable: true, threshold: None },
+            KindSpec{ kind: CellKind::Wall, symbol: '#', occupies: true, removable: false, threshold: None },
+            KindSpec{ kind: CellKind::Rack, symbol: 'R', occupies: true, removable: false, threshold: None },
+        ]);
+        kinds
+    }
+
+    fn spec(&self, kind: CellKind) -> Option<&KindSpec> {
+        self.specs.iter().find(|spec| spec.kind == kind)
+    }
+
+    fn kind_of(&self, symbol: char) -> Option<CellKind> {
+        self.specs.iter().find(|spec| spec.symbol == symbol).map(|spec| spec.kind)
+    }
+
+    fn symbol_of(&self, kind: CellKind) -> char {
+        self.spec(kind).map_or('?', |spec| spec.symbol)
+    }
+
+    // adds a kind, or replaces how an existing kind behaves
+    fn set(&mut self, spec: KindSpec) -> Result<(), String> {
+        if let Some(other) = self.specs.iter().find(|other| other.symbol == spec.symbol && other.kind != spec.kind) {
+            return Err(format!("Symbol '{}' is already used for {}", spec.symbol, other.kind.name()));
+        }
+        match self.specs.iter_mut().find(|other| other.kind == spec.kind) {
+            Some(existing) => *existing = spec,
+            None => self.specs.push(spec),
+        }
+        Ok(())
+    }
+
+    fn set_alphabet(&mut self, alphabet: GridAlphabet) -> Result<(), String> {
+        let roll = *self.spec(CellKind::Roll).ok_or("No roll kind to draw")?;
+        let floor = *self.spec(CellKind::Floor).ok_or("No floor kind to draw")?;
+        // take both out first so the two symbols can be swapped
+        self.specs.retain(|spec| spec.kind != CellKind::Roll && spec.kind != CellKind::Floor);
+        self.set(KindSpec{ symbol: alphabet.floor, ..floor })?;
+        self.set(KindSpec{ symbol: alphabet.roll, ..roll })
+    }
+}
+
+// how rolls are taken away once they become reachable. a generation removes every roll
+// reachable in the current grid at once, greedy removes them one by one so each removal
+// can free its neighbours straight away. under the puzzle rule both end with the same rolls left
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PeelMode { Generations, Greedy }
+
+// which cells around a roll count as its neighbours
+#[derive(Debug, Clone, PartialEq)]
+enum Neighbourhood {
+    // the 4 orthogonal cells
+    VonNeumann,
+    // every cell within r steps in both directions, so 8 cells for r = 1
+    Moore(u32),
+    Custom(Vec<(i64, i64)>),
+}
+
+impl Neighbourhood {
+    fn offsets(&self) -> Vec<(i64, i64)> {
+        match self {
+            Neighbourhood::VonNeumann => vec![(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Neighbourhood::Moore(radius) => {
+                let r = *radius as i64;
+                (-r..=r).flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+                    .filter(|offset| *offset != (0, 0))
+                    .collect()
+            }
+            Neighbourhood::Custom(offsets) => offsets.clone(),
+        }
+    }
+}
+
+// a moore neighbourhood has (2r + 1)^2 - 1 cells, so the radius is kept small
+const MAX_MOORE_RADIUS: u32 = 16;
+
+// von-neumann, moore, moore:<radius> or custom:<dx>,<dy>;<dx>,<dy>;...
+impl FromStr for Neighbourhood {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "von-neumann" => Ok(Neighbourhood::VonNeumann),
+            None if s == "moore" => Ok(Neighbourhood::Moore(1)),
+            Some(("moore", radius)) => match radius.parse() {
+                Ok(radius) if radius <= MAX_MOORE_RADIUS => Ok(Neighbourhood::Moore(radius)),
+                Ok(_) => Err("Moore radius is larger than 16"),
+                Err(_) => Err("Moore radius is not a number"),
+            },
+            Some(("custom", offsets)) => offsets.split(';')
+                .map(|offset| {
+                    let (dx, dy) = offset.split_once(',').ok_or("Offset must be <dx>,<dy>")?;
+                    let dx = dx.trim().parse().map_err(|_| "Offset is not a number")?;
+                    let dy = dy.trim().parse().map_err(|_| "Offset is not a number")?;
+                    Ok((dx, dy))
+                })
+                .collect::<Result<Vec<(i64, i64)>, &'static str>>()
+                .map(Neighbourhood::Custom),
+            _ => Err("Unknown neighbourhood"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison { Less, LessOrEqual, Equal, GreaterOrEqual, Greater }
+
+impl Comparison {
+    fn holds(&self, count: usize, threshold: usize) -> bool {
+        match self {
+            Comparison::Less => count < threshold,
+            Comparison::LessOrEqual => count <= threshold,
+            Comparison::Equal => count == threshold,
+            Comparison::GreaterOrEqual => count >= threshold,
+            Comparison::Greater => count > threshold,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lt" => Ok(Comparison::Less),
+            "le" => Ok(Comparison::LessOrEqual),
+            "eq" => Ok(Comparison::Equal),
+            "ge" => Ok(Comparison::GreaterOrEqual),
+            "gt" => Ok(Comparison::Greater),
+            _ => Err("Comparison must be one of lt, le, eq, ge or gt"),
+        }
+    }
+}
+
+// a roll can be removed when the number of rolls in its neighbourhood compares to the
+// threshold. the puzzle rule is fewer than 4 rolls among the 8 surrounding cells
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    neighbourhood: Neighbourhood,
+    threshold: usize,
+    comparison: Comparison,
+    // whether the grid wraps round at the edges as a torus
+    wrap: bool
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule{ neighbourhood: Neighbourhood::Moore(1), threshold: 4, comparison: Comparison::Less, wrap: false }
+    }
+}
 
-// Written by Claude
 impl fmt::Display for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.coords.is_empty() {
-            return Ok(());
-        }
-        
-        let max_x = self.coords.iter().map(|c| c.x).max().unwrap_or(0);
-        let max_y = self.coords.iter().map(|c| c.y).max().unwrap_or(0);
-        
-        for y in 0..=max_y {
-            for x in 0..=max_x {
-                let coord = self.coords.iter()
-                    .find(|c| c.x == x && c.y == y);
-                
-                match coord {
-                    Some(c) if c.has_paper_roll => write!(f, "@")?,
-                    _ => write!(f, ".")?,
-                }
+        for (y, row) in self.coords.chunks(self.width.max(1)).enumerate() {
+            for coord in row {
+                write!(f, "{}", self.kinds.symbol_of(coord.kind))?;
             }
-            if y < max_y {
+            if y + 1 < self.height {
                 writeln!(f)?;
             }
         }
@@ -37,103 +256,722 @@ impl fmt::Display for Grid {
 }
 
 impl Grid {
-    
+    // places coordinates given in any order, filling cells that are missing with empty floor
+    fn from_coords(coords: Vec<Coordinate>) -> Grid {
+        let width = coords.iter().map(|c| c.x as usize + 1).max().unwrap_or(0);
+        let height = coords.iter().map(|c| c.y as usize + 1).max().unwrap_or(0);
+        let mut cells: Vec<Coordinate> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Coordinate{ x: x as u32, y: y as u32, kind: CellKind::Floor }))
+            .collect();
+        for coord in coords {
+            let index = coord.y as usize * width + coord.x as usize;
+            cells[index] = coord;
+        }
+        let rule = Rule::default();
+        let offsets = rule.neighbourhood.offsets();
+        Grid{ coords: cells, width, height, rule, offsets, kinds: CellKinds::default() }
+    }
+
+    fn occupies(&self, coord: &Coordinate) -> bool {
+        self.kinds.spec(coord.kind).is_some_and(|spec| spec.occupies)
+    }
+
+    // the threshold a cell is removed against, or None if it can never be removed
+    fn threshold(&self, coord: &Coordinate) -> Option<usize> {
+        self.kinds.spec(coord.kind)
+            .filter(|spec| spec.removable)
+            .map(|spec| spec.threshold.unwrap_or(self.rule.threshold))
+    }
+
+    fn with_rule(self, rule: Rule) -> Grid {
+        let offsets = rule.neighbourhood.offsets();
+        Grid{ rule, offsets, ..self }
+    }
+
+    fn index_of(&self, x: i64, y: i64) -> Option<usize> {
+        let (x, y) = if self.rule.wrap && self.width > 0 && self.height > 0 {
+            (x.rem_euclid(self.width as i64), y.rem_euclid(self.height as i64))
+        } else {
+            (x, y)
+        };
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    // on a torus smaller than the neighbourhood several offsets can land on the same cell,
+    // or on the cell itself, so each neighbour is only counted once
+    fn neighbour_indices(&self, coord: &Coordinate) -> Vec<usize> {
+        let (x, y) = (coord.x as i64, coord.y as i64);
+        let own_index = y as usize * self.width + x as usize;
+        let mut indices: Vec<usize> = self.offsets.iter()
+            .filter_map(|(dx, dy)| self.index_of(x + dx, y + dy))
+            .filter(|index| *index != own_index)
+            .collect();
+        if self.rule.wrap || matches!(self.rule.neighbourhood, Neighbourhood::Custom(_)) {
+            let mut seen = HashSet::new();
+            indices.retain(|index| seen.insert(*index));
+        }
+        indices
+    }
 
     fn neighbours(&self, coord: &Coordinate) -> Vec<&Coordinate> {
-        self.coords.iter().filter(|maybe_neighbour| {
-            if maybe_neighbour.x == coord.x && maybe_neighbour.y == coord.y {
-                return false;
-            }
-            let x_dist = maybe_neighbour.x.abs_diff(coord.x);
-            let y_dist = maybe_neighbour.y.abs_diff(coord.y);
-            x_dist <= 1 && y_dist <= 1
-        }).collect()
+        self.neighbour_indices(coord).into_iter().map(|index| &self.coords[index]).collect()
     }
 
+    // the neighbours holding anything that counts as occupied, not just rolls
     fn neighbours_with_rolls(&self, coord: &Coordinate) -> Vec<&Coordinate> {
-        self.neighbours(coord).into_iter().filter(|c| c.has_paper_roll).collect()
+        self.neighbours(coord).into_iter().filter(|c| self.occupies(c)).collect()
     }
 
     fn removable_rolls(&self) -> Vec<Coordinate> {
         self.coords.iter()
-            .filter(|coord| coord.has_paper_roll)
-            .filter(|coord| self.neighbours_with_rolls(coord).len() < 4)
+            .filter(|coord| self.threshold(coord)
+                .is_some_and(|threshold| self.rule.comparison.holds(self.neighbours_with_rolls(coord).len(), threshold)))
             .cloned()
             .collect()
     }
 
+    // a single generation done the slow way, kept to check the incremental peel against
+    #[cfg(test)]
     fn remove_rolls(&self, manouverable_coords: &[Coordinate]) -> Grid {
-        let removed_rolls = self.coords.iter().map(|coord| {
-            if manouverable_coords.iter().any(|c| c.x == coord.x && c.y == coord.y) {
-                Coordinate{ x: coord.x, y: coord.y, has_paper_roll: false }
-            } else {
-                coord.clone()
+        let mut removed_rolls = self.clone();
+        for coord in manouverable_coords {
+            let index = coord.y as usize * self.width + coord.x as usize;
+            if let Some(cell) = removed_rolls.coords.get_mut(index) {
+                cell.kind = CellKind::Floor;
             }
-        }).collect();
-        Grid{ coords: removed_rolls }
+        }
+        removed_rolls
     }
-}
 
-// Written by Claude
-fn parse_grid(raw_grid: Vec<&str>) -> Grid {
-    let coords: Vec<Coordinate> = raw_grid.iter()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(x, char)| Coordinate {
-                    x: x as u32,
-                    y: y as u32,
-                    has_paper_roll: char == '@',
+    // removes reachable rolls until none are left, returning the rolls removed at each step.
+    // neighbour counts are kept per cell and only the cells watching a removed roll are
+    // updated and queued to be checked again, so the whole peel is linear in the grid size
+    fn peel_rolls(&self, mode: PeelMode) -> Vec<Vec<Coordinate>> {
+        let neighbours: Vec<Vec<usize>> = self.coords.iter().map(|coord| self.neighbour_indices(coord)).collect();
+        // a custom neighbourhood need not be symmetric, so track who counts each cell
+        let mut watchers: Vec<Vec<usize>> = vec![vec![]; self.coords.len()];
+        for (index, indices) in neighbours.iter().enumerate() {
+            for neighbour in indices {
+                watchers[*neighbour].push(index);
+            }
+        }
+        let mut occupied: Vec<bool> = self.coords.iter().map(|coord| self.occupies(coord)).collect();
+        // None once a cell has gone, or if it never can
+        let mut thresholds: Vec<Option<usize>> = self.coords.iter().map(|coord| self.threshold(coord)).collect();
+        let mut counts: Vec<usize> = neighbours.iter()
+            .map(|indices| indices.iter().filter(|index| occupied[**index]).count())
+            .collect();
+        let removable = |index: usize, thresholds: &[Option<usize>], counts: &[usize]| {
+            thresholds[index].is_some_and(|threshold| self.rule.comparison.holds(counts[index], threshold))
+        };
+        let mut queued: Vec<bool> = (0..self.coords.len()).map(|index| removable(index, &thresholds, &counts)).collect();
+        let mut queue: VecDeque<usize> = (0..self.coords.len()).filter(|index| queued[*index]).collect();
+
+        let mut steps = vec![];
+        while !queue.is_empty() {
+            let candidates: Vec<usize> = match mode {
+                PeelMode::Generations => queue.drain(..).collect(),
+                PeelMode::Greedy => queue.pop_front().into_iter().collect(),
+            };
+            // a rule that is not "fewer than" can stop holding as neighbours disappear
+            let removed: Vec<usize> = candidates.into_iter()
+                .filter(|index| {
+                    queued[*index] = false;
+                    removable(*index, &thresholds, &counts)
                 })
+                .collect();
+            for index in &removed {
+                thresholds[*index] = None;
+            }
+            for index in &removed {
+                if !occupied[*index] {
+                    continue;
+                }
+                occupied[*index] = false;
+                for watcher in &watchers[*index] {
+                    counts[*watcher] -= 1;
+                    if thresholds[*watcher].is_some() && !queued[*watcher] {
+                        queued[*watcher] = true;
+                        queue.push_back(*watcher);
+                    }
+                }
+            }
+            if !removed.is_empty() {
+                steps.push(removed.into_iter().map(|index| self.coords[index].clone()).collect());
+            }
+        }
+        steps
+    }
+}
+
+// what became of each cell once the grid has been peeled
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellFate {
+    Floor,
+    // removed in the given step, counting from 1
+    Removed(usize),
+    // part of the core of rolls that can never be removed
+    Stable,
+    // a kind that never moves, such as a wall
+    Fixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MapFormat { Text, Csv, Pgm, Ppm }
+
+impl FromStr for MapFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(MapFormat::Text),
+            "csv" => Ok(MapFormat::Csv),
+            "pgm" => Ok(MapFormat::Pgm),
+            "ppm" => Ok(MapFormat::Ppm),
+            _ => Err("Map format must be one of text, csv, pgm or ppm"),
+        }
+    }
+}
+
+const GENERATION_SYMBOLS: &[u8] = b"123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+struct GenerationMap { fates: Vec<CellFate>, width: usize, height: usize }
+
+impl GenerationMap {
+    fn from_steps(grid: &Grid, steps: &[Vec<Coordinate>]) -> GenerationMap {
+        let mut fates: Vec<CellFate> = grid.coords.iter()
+            .map(|coord| match (grid.threshold(coord), grid.occupies(coord)) {
+                (Some(_), _) => CellFate::Stable,
+                (None, true) => CellFate::Fixed,
+                (None, false) => CellFate::Floor,
+            })
+            .collect();
+        for (step, removed) in steps.iter().enumerate() {
+            for coord in removed {
+                fates[coord.y as usize * grid.width + coord.x as usize] = CellFate::Removed(step + 1);
+            }
+        }
+        GenerationMap{ fates, width: grid.width, height: grid.height }
+    }
+
+    fn last_generation(&self) -> usize {
+        self.fates.iter()
+            .filter_map(|fate| match fate { CellFate::Removed(generation) => Some(*generation), _ => None })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[CellFate]> {
+        self.fates.chunks(self.width.max(1))
+    }
+
+    // one symbol per cell: generations 1 to 9 then letters, '+' once those run out,
+    // '#' for the stable core, '=' for fixed cells and '.' for floor
+    fn to_text(&self) -> String {
+        self.rows().map(|row| row.iter().map(|fate| match fate {
+            CellFate::Floor => '.',
+            CellFate::Stable => '#',
+            CellFate::Fixed => '=',
+            CellFate::Removed(generation) => GENERATION_SYMBOLS.get(generation - 1).map_or('+', |symbol| *symbol as char),
+        }).collect::<String>() + "\n").collect()
+    }
+
+    // one line per occupied cell, with the generation left as "stable" for the core and
+    // "fixed" for cells that never move
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("x,y,generation\n");
+        for (index, fate) in self.fates.iter().enumerate() {
+            let (x, y) = (index % self.width, index / self.width);
+            match fate {
+                CellFate::Floor => {}
+                CellFate::Removed(generation) => csv += &format!("{},{},{}\n", x, y, generation),
+                CellFate::Stable => csv += &format!("{},{},stable\n", x, y),
+                CellFate::Fixed => csv += &format!("{},{},fixed\n", x, y),
+            }
+        }
+        csv
+    }
+
+    // floor is black and the stable core white, with removed rolls getting brighter
+    // the later they go and fixed cells a dim grey below them all
+    fn levels(&self) -> impl Iterator<Item = u8> + '_ {
+        let spread = self.last_generation().max(2) - 1;
+        self.fates.iter().map(move |fate| match fate {
+            CellFate::Floor => 0,
+            CellFate::Fixed => 20,
+            CellFate::Stable => 255,
+            CellFate::Removed(generation) => (40 + (generation - 1) * 160 / spread) as u8,
+        })
+    }
+
+    fn to_pgm(&self) -> Vec<u8> {
+        let mut image = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        image.extend(self.levels());
+        image
+    }
+
+    // as the pgm, but with the stable core in red so it stands apart from late removals,
+    // and fixed cells in blue
+    fn to_ppm(&self) -> Vec<u8> {
+        let mut image = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for (fate, level) in self.fates.iter().zip(self.levels()) {
+            match fate {
+                CellFate::Stable => image.extend([255, 0, 0]),
+                CellFate::Fixed => image.extend([0x40, 0x40, 0xff]),
+                _ => image.extend([level; 3]),
+            }
+        }
+        image
+    }
+
+    fn render(&self, format: MapFormat) -> Vec<u8> {
+        match format {
+            MapFormat::Text => self.to_text().into_bytes(),
+            MapFormat::Csv => self.to_csv().into_bytes(),
+            MapFormat::Pgm => self.to_pgm(),
+            MapFormat::Ppm => self.to_ppm(),
+        }
+    }
+}
+
+type Colour = [u8; 3];
+
+// a colour as six hex digits, with or without a leading '#'
+fn parse_colour(s: &str) -> Result<Colour, &'static str> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err("Colour must be six hex digits");
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| "Colour must be six hex digits");
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// palette indices used for each cell of a frame
+const FLOOR: u8 = 0;
+const ROLL: u8 = 1;
+const REMOVING: u8 = 2;
+const FIXED: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+struct FrameStyle {
+    // pixels along each side of a cell
+    cell_size: usize,
+    floor: Colour,
+    roll: Colour,
+    // rolls about to be removed in the next step
+    removing: Colour,
+    // cells that never move
+    fixed: Colour
+}
+
+impl Default for FrameStyle {
+    fn default() -> Self {
+        FrameStyle{ cell_size: 4, floor: [0x20, 0x20, 0x20], roll: [0xd0, 0xd0, 0xd0], removing: [0xe0, 0x40, 0x40], fixed: [0x50, 0x60, 0x90] }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameFormat { Pgm, Ppm }
+
+impl FromStr for FrameFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pgm" => Ok(FrameFormat::Pgm),
+            "ppm" => Ok(FrameFormat::Ppm),
+            _ => Err("Frame format must be pgm or ppm"),
+        }
+    }
+}
+
+// hands each frame to visit as it is drawn, the grid before each step and once peeling
+// is done, one palette index per cell. only the current frame is ever held
+fn for_each_frame<F>(grid: &Grid, steps: &[Vec<Coordinate>], mut visit: F) -> Result<(), String>
+where
+    F: FnMut(&[u8]) -> Result<(), String>,
+{
+    let mut cells: Vec<u8> = grid.coords.iter()
+        .map(|coord| match (grid.threshold(coord), grid.occupies(coord)) {
+            (Some(_), _) => ROLL,
+            (None, true) => FIXED,
+            (None, false) => FLOOR,
         })
         .collect();
-    Grid{ coords }
+    for step in steps {
+        for coord in step {
+            cells[coord.y as usize * grid.width + coord.x as usize] = REMOVING;
+        }
+        visit(&cells)?;
+        for coord in step {
+            cells[coord.y as usize * grid.width + coord.x as usize] = FLOOR;
+        }
+    }
+    visit(&cells)
+}
+
+impl FrameStyle {
+    fn palette(&self) -> [Colour; 4] {
+        [self.floor, self.roll, self.removing, self.fixed]
+    }
+
+    // blows each cell up into a cell_size square of pixels
+    fn scale(&self, cells: &[u8], width: usize) -> Vec<u8> {
+        cells.chunks(width.max(1))
+            .flat_map(|row| {
+                let pixel_row: Vec<u8> = row.iter().flat_map(|cell| std::iter::repeat_n(*cell, self.cell_size)).collect();
+                std::iter::repeat_n(pixel_row, self.cell_size).flatten()
+            })
+            .collect()
+    }
+
+    fn render(&self, cells: &[u8], width: usize, height: usize, format: FrameFormat) -> Vec<u8> {
+        let (pixel_width, pixel_height) = (width * self.cell_size, height * self.cell_size);
+        let palette = self.palette();
+        let pixels = self.scale(cells, width);
+        match format {
+            FrameFormat::Pgm => {
+                let mut image = format!("P5\n{} {}\n255\n", pixel_width, pixel_height).into_bytes();
+                let greys = palette.map(|[r, g, b]| ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8);
+                image.extend(pixels.iter().map(|pixel| greys[*pixel as usize]));
+                image
+            }
+            FrameFormat::Ppm => {
+                let mut image = format!("P6\n{} {}\n255\n", pixel_width, pixel_height).into_bytes();
+                image.extend(pixels.iter().flat_map(|pixel| palette[*pixel as usize]));
+                image
+            }
+        }
+    }
+}
+
+// writes a looping gif animation one frame at a time, each shown for delay hundredths
+// of a second
+struct GifWriter<W: Write> {
+    out: W,
+    style: FrameStyle,
+    width: usize,
+    gif_width: u16,
+    gif_height: u16,
+    delay: u16
+}
+
+impl<W: Write> GifWriter<W> {
+    fn new(mut out: W, style: &FrameStyle, width: usize, height: usize, delay: u16) -> Result<GifWriter<W>, String> {
+        let (pixel_width, pixel_height) = (width * style.cell_size, height * style.cell_size);
+        let (gif_width, gif_height) = match (u16::try_from(pixel_width), u16::try_from(pixel_height)) {
+            (Ok(gif_width), Ok(gif_height)) => (gif_width, gif_height),
+            _ => return Err(format!("A {}x{} image is too large for a gif", pixel_width, pixel_height)),
+        };
+
+        let mut header = b"GIF89a".to_vec();
+        header.extend(gif_width.to_le_bytes());
+        header.extend(gif_height.to_le_bytes());
+        // a global colour table of the 4 palette entries
+        header.extend([0x91, 0, 0]);
+        for colour in style.palette() {
+            header.extend(colour);
+        }
+        // loop forever
+        header.extend([0x21, 0xff, 0x0b]);
+        header.extend(b"NETSCAPE2.0");
+        header.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+        out.write_all(&header).map_err(|e| e.to_string())?;
+
+        Ok(GifWriter{ out, style: style.clone(), width, gif_width, gif_height, delay })
+    }
+
+    fn frame(&mut self, cells: &[u8]) -> Result<(), String> {
+        let mut frame = vec![0x21, 0xf9, 0x04, 0x00];
+        frame.extend(self.delay.to_le_bytes());
+        frame.extend([0x00, 0x00]);
+        frame.push(0x2c);
+        frame.extend([0, 0, 0, 0]);
+        frame.extend(self.gif_width.to_le_bytes());
+        frame.extend(self.gif_height.to_le_bytes());
+        frame.push(0x00);
+        frame.push(GIF_MIN_CODE_SIZE);
+        for block in lzw_compress(&self.style.scale(cells, self.width), GIF_MIN_CODE_SIZE).chunks(255) {
+            frame.push(block.len() as u8);
+            frame.extend(block);
+        }
+        frame.push(0x00);
+        self.out.write_all(&frame).map_err(|e| e.to_string())
+    }
+
+    fn finish(mut self) -> Result<W, String> {
+        self.out.write_all(&[0x3b]).and_then(|_| self.out.flush()).map_err(|e| e.to_string())?;
+        Ok(self.out)
+    }
+}
+
+const GIF_MIN_CODE_SIZE: u8 = 2;
+const GIF_MAX_CODE: u16 = 4096;
+
+// variable width lzw as gif uses it: codes are packed least significant bit first and
+// grow a bit each time the table passes a power of two, starting over once it is full
+fn lzw_compress(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = min_code_size as u32 + 1;
+
+    let mut bytes = vec![];
+    let mut buffer: u32 = 0;
+    let mut buffered_bits = 0;
+    let mut emit = |code: u16, code_size: u32| {
+        buffer |= (code as u32) << buffered_bits;
+        buffered_bits += code_size;
+        while buffered_bits >= 8 {
+            bytes.push(buffer as u8);
+            buffer >>= 8;
+            buffered_bits -= 8;
+        }
+    };
+
+    emit(clear, code_size);
+    if let Some((first, rest)) = indices.split_first() {
+        let mut prefix = *first as u16;
+        for index in rest {
+            if let Some(code) = table.get(&(prefix, *index)) {
+                prefix = *code;
+                continue;
+            }
+            emit(prefix, code_size);
+            if next_code < GIF_MAX_CODE {
+                table.insert((prefix, *index), next_code);
+                next_code += 1;
+                if next_code > 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            } else {
+                emit(clear, code_size);
+                table.clear();
+                next_code = end + 1;
+                code_size = min_code_size as u32 + 1;
+            }
+            prefix = *index as u16;
+        }
+        emit(prefix, code_size);
+    }
+    emit(end, code_size);
+    emit(0, 7);
+    bytes
+}
+
+// the characters a grid is drawn with
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GridAlphabet { roll: char, floor: char }
+
+impl Default for GridAlphabet {
+    fn default() -> Self {
+        GridAlphabet{ roll: '@', floor: '.' }
+    }
+}
+
+// the roll character followed by the floor character, such as "@."
+impl FromStr for GridAlphabet {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        match chars[..] {
+            [roll, floor] if roll != floor => Ok(GridAlphabet{ roll, floor }),
+            _ => Err("Alphabet must be two different characters, a roll then floor"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct GridFormat {
+    kinds: CellKinds,
+    // pad short rows with floor instead of rejecting them
+    lenient: bool
+}
+
+// every row must be as long as the first, unless lenient, when rows are padded out to
+// the longest one
+fn parse_grid(raw_grid: Vec<&str>, format: &GridFormat) -> Result<Grid, String> {
+    let lengths: Vec<usize> = raw_grid.iter().map(|line| line.chars().count()).collect();
+    let width = if format.lenient { lengths.iter().copied().max() } else { lengths.first().copied() }.unwrap_or(0);
+
+    let mut coords = Vec::with_capacity(width * raw_grid.len());
+    for (y, line) in raw_grid.iter().enumerate() {
+        if !format.lenient && lengths[y] != width {
+            let column = lengths[y].min(width) + 1;
+            return Err(format!("Row {}, column {}: Expected {} cells but found {}", y + 1, column, width, lengths[y]));
+        }
+        for (x, char) in line.chars().enumerate() {
+            let kind = format.kinds.kind_of(char)
+                .ok_or(format!("Row {}, column {}: Unexpected character '{}'", y + 1, x + 1, char))?;
+            coords.push(Coordinate{ x: x as u32, y: y as u32, kind });
+        }
+        coords.extend((lengths[y]..width).map(|x| Coordinate{ x: x as u32, y: y as u32, kind: CellKind::Floor }));
+    }
+    let grid = Grid::from_coords(coords);
+    Ok(Grid{ kinds: format.kinds.clone(), ..grid })
 }
 
-fn part_one(input: &str) -> Result<usize, String> {
+fn load_grid(input: &str, options: &Options) -> Result<Grid, String> {
     let lines: Vec<&str> = input.lines().collect();
-    let grid = parse_grid(lines);
+    Ok(parse_grid(lines, &options.grid_format)?.with_rule(options.rule.clone()))
+}
+
+fn part_one(input: &str, options: &Options) -> Result<usize, String> {
+    let grid = load_grid(input, options)?;
     let manouverable_coords = &grid.removable_rolls();
     Ok(manouverable_coords.len())
 }
 
-fn part_two(input: &str) -> Result<usize, String> {
-    let lines: Vec<&str> = input.lines().collect();
-    let initial_grid = parse_grid(lines);
+fn generation_map(input: &str, options: &Options) -> Result<GenerationMap, String> {
+    let grid = load_grid(input, options)?;
+    Ok(GenerationMap::from_steps(&grid, &grid.peel_rolls(options.mode)))
+}
 
-    let grids_with_counts: Vec<usize> = std::iter::successors(
-        Some(initial_grid.clone()),
-        |current| {
-            let removable = current.removable_rolls();
-            if removable.is_empty() {
-                None
-            } else {
-                Some(current.remove_rolls(&removable))
-            }
+// writes each frame file and gif frame as soon as the frame is drawn
+fn write_frames(input: &str, options: &Options) -> Result<(), String> {
+    let grid = load_grid(input, options)?;
+    let steps = grid.peel_rolls(options.mode);
+    if let Some((_, directory)) = &options.frames {
+        std::fs::create_dir_all(directory).map_err(|e| format!("Cannot create {}: {}", directory, e))?;
+    }
+    let mut gif = match &options.gif {
+        Some(path) => Some(GifWriter::new(open_output(path)?, &options.style, grid.width, grid.height, options.delay)?),
+        None => None,
+    };
+
+    let mut index = 0;
+    for_each_frame(&grid, &steps, |cells| {
+        if let Some((format, directory)) = &options.frames {
+            let extension = match format { FrameFormat::Pgm => "pgm", FrameFormat::Ppm => "ppm" };
+            let path = format!("{}/frame-{:04}.{}", directory, index, extension);
+            write_output(&path, &options.style.render(cells, grid.width, grid.height, *format))?;
         }
-    )
-    .inspect(
-        |grid| {
-        println!("Grid state:\n {} \n", grid);
-    })
-    .map(|grid| {
-        let removable = grid.removable_rolls();
-        
-        removable.len()
-    })
-    .collect();
+        if let Some(gif) = &mut gif {
+            gif.frame(cells)?;
+        }
+        index += 1;
+        Ok(())
+    })?;
+
+    if let Some(gif) = gif {
+        gif.finish()?;
+    }
+    Ok(())
+}
+
+fn open_output(path: &str) -> Result<Box<dyn Write>, String> {
+    if path == "-" {
+        return Ok(Box::new(io::stdout()));
+    }
+    let file = std::fs::File::create(path).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+    Ok(Box::new(io::BufWriter::new(file)))
+}
+
+fn write_output(path: &str, bytes: &[u8]) -> Result<(), String> {
+    if path == "-" {
+        io::stdout().write_all(bytes).map_err(|e| e.to_string())
+    } else {
+        std::fs::write(path, bytes).map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+}
 
-    let removed_roll_count = grids_with_counts.iter().sum();
-    
+fn part_two(input: &str, options: &Options) -> Result<usize, String> {
+    let grid = load_grid(input, options)?;
+    let removed_roll_count = grid.peel_rolls(options.mode).iter().map(|step| step.len()).sum();
     Ok(removed_roll_count)
 }
 
-fn main() {
-    let result = part_one(INPUT);
+struct Options {
+    mode: PeelMode,
+    rule: Rule,
+    grid_format: GridFormat,
+    // where to write the generation map, - for stdout
+    map: Option<(MapFormat, String)>,
+    // a directory for numbered frames of each step
+    frames: Option<(FrameFormat, String)>,
+    gif: Option<String>,
+    style: FrameStyle,
+    // hundredths of a second per gif frame
+    delay: u16
+}
+
+fn parse_number<T: FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number '{}' after {}", value, flag))
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options{
+            mode: PeelMode::Generations,
+            rule: Rule::default(),
+            grid_format: GridFormat::default(),
+            map: None,
+            frames: None,
+            gif: None,
+            style: FrameStyle::default(),
+            delay: 20
+        }
+    }
+}
+
+impl Options {
+    fn from_args(args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value after {}", arg));
+            match arg.as_str() {
+                "--greedy" => options.mode = PeelMode::Greedy,
+                "--wrap" => options.rule.wrap = true,
+                "--lenient" => options.grid_format.lenient = true,
+                "--alphabet" => options.grid_format.kinds.set_alphabet(value()?.parse()?)?,
+                "--warehouse" => options.grid_format.kinds = CellKinds::warehouse(),
+                "--kind" => options.grid_format.kinds.set(value()?.parse()?)?,
+                "--neighbourhood" => options.rule.neighbourhood = value()?.parse()?,
+                "--comparison" => options.rule.comparison = value()?.parse()?,
+                "--map" => {
+                    let format = value()?.parse()?;
+                    options.map = Some((format, value()?.clone()));
+                }
+                "--threshold" => options.rule.threshold = parse_number(value()?, arg)?,
+                "--frames" => {
+                    let format = value()?.parse()?;
+                    options.frames = Some((format, value()?.clone()));
+                }
+                "--gif" => options.gif = Some(value()?.clone()),
+                "--cell-size" => options.style.cell_size = parse_number(value()?, arg)?,
+                "--delay" => options.delay = parse_number(value()?, arg)?,
+                "--floor-colour" => options.style.floor = parse_colour(value()?)?,
+                "--roll-colour" => options.style.roll = parse_colour(value()?)?,
+                "--removing-colour" => options.style.removing = parse_colour(value()?)?,
+                "--fixed-colour" => options.style.fixed = parse_colour(value()?)?,
+                _ => return Err(format!("Unexpected argument: {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = Options::from_args(&args)?;
+    let result = part_one(INPUT, &options);
     println!("Part one: {:?}", result);
-    let result = part_two(INPUT);
+    let result = part_two(INPUT, &options);
     println!("Part two: {:?}", result);
+    if let Some((format, path)) = &options.map {
+        let map = generation_map(INPUT, &options)?;
+        write_output(path, &map.render(*format))?;
+    }
+    if options.frames.is_some() || options.gif.is_some() {
+        write_frames(INPUT, &options)?;
+    }
+    Ok(())
 }
 
 // Written by Claude
@@ -144,7 +982,7 @@ mod tests {
     #[test]
     fn test_part_one() {
         // Given
-        let input = vec![
+        let input = [
             "..@@.@@@@.",
             "@@@.@.@.@@",
             "@@@@@.@.@@",
@@ -158,7 +996,7 @@ mod tests {
         ].join("\n");
         
         // When
-        let result = part_one(&input).unwrap();
+        let result = part_one(&input, &Options::default()).unwrap();
         
         // Then
         assert_eq!(result, 13);
@@ -167,7 +1005,7 @@ mod tests {
     #[test]
     fn test_part_two() {
         // Given
-        let input = vec![
+        let input = [
             "..@@.@@@@.",
             "@@@.@.@.@@",
             "@@@@@.@.@@",
@@ -181,10 +1019,11 @@ mod tests {
         ].join("\n");
         
         // When
-        let result = part_two(&input).unwrap();
+        let result = part_two(&input, &Options::default()).unwrap();
         
         // Then
         assert_eq!(result, 43);
+        assert_eq!(part_two(&input, &Options{ mode: PeelMode::Greedy, ..Options::default() }), Ok(43));
     }
 
     #[test]
@@ -198,22 +1037,21 @@ mod tests {
         let mut coords = Vec::new();
         for y in 0..5 {
             for x in 0..5 {
-                let has_roll = match (x, y) {
-                    (0, 0) | (2, 0) | (3, 0) => true,
-                    (0, 1) | (1, 1) | (3, 1) => true,
-                    (1, 2) | (2, 2) | (3, 2) | (4, 2) => true,
-                    (0, 3) | (2, 3) | (4, 3) => true,
-                    (1, 4) | (2, 4) | (3, 4) => true,
-                    _ => false,
-                };
+                let has_roll = matches!((x, y),
+                    (0, 0) | (2, 0) | (3, 0) |
+                    (0, 1) | (1, 1) | (3, 1) |
+                    (1, 2) | (2, 2) | (3, 2) | (4, 2) |
+                    (0, 3) | (2, 3) | (4, 3) |
+                    (1, 4) | (2, 4) | (3, 4)
+                );
                 coords.push(Coordinate {
                     x,
                     y,
-                    has_paper_roll: has_roll,
+                    kind: if has_roll { CellKind::Roll } else { CellKind::Floor },
                 });
             }
         }
-        let grid = Grid { coords };
+        let grid = Grid::from_coords(coords);
         
         // When
         let result = grid.removable_rolls();
@@ -226,7 +1064,7 @@ mod tests {
         // Then - check corner coord (0,0) has 2 neighbours with rolls: (0,1) and (1,1)
         let corner_neighbours_with_rolls = grid.neighbours_with_rolls(corner);
         assert_eq!(corner_neighbours_with_rolls.len(), 2); // (0,1) and (1,1)
-        assert!(corner_neighbours_with_rolls.iter().all(|c| c.has_paper_roll));
+        assert!(corner_neighbours_with_rolls.iter().all(|c| c.kind == CellKind::Roll));
         
         // Then - check manouverable coords count
         assert_eq!(result.len(), 10);
@@ -247,11 +1085,11 @@ mod tests {
                 coords.push(Coordinate {
                     x,
                     y,
-                    has_paper_roll: has_roll,
+                    kind: if has_roll { CellKind::Roll } else { CellKind::Floor },
                 });
             }
         }
-        let grid = Grid { coords };
+        let grid = Grid::from_coords(coords);
         
         // When
         let result = grid.removable_rolls();
@@ -283,17 +1121,15 @@ mod tests {
         let mut coords = Vec::new();
         for y in 0..5 {
             for x in 0..5 {
-                let has_roll = (x == 2 && y == 1) || 
-                               (x == 1 && y == 2) || (x == 2 && y == 2) || (x == 3 && y == 2) ||
-                               (x == 2 && y == 3);
+                let has_roll = matches!((x, y), (2, 1) | (1, 2) | (2, 2) | (3, 2) | (2, 3));
                 coords.push(Coordinate {
                     x,
                     y,
-                    has_paper_roll: has_roll,
+                    kind: if has_roll { CellKind::Roll } else { CellKind::Floor },
                 });
             }
         }
-        let grid = Grid { coords };
+        let grid = Grid::from_coords(coords);
         
         // When
         let result = grid.removable_rolls();
@@ -314,4 +1150,656 @@ mod tests {
         // Then - check manouverable coords count (all edge coords have < 4 neighbours with rolls)
         assert_eq!(result.len(), 4);
     }
+
+    #[test]
+    fn test_display_round_trips_and_pads_short_rows() {
+        // Given
+        let grid = parse_grid(vec!["@@.", "@", ".@@"], &GridFormat{ lenient: true, ..GridFormat::default() }).unwrap();
+
+        // When
+        let result = grid.to_string();
+
+        // Then
+        assert_eq!(result, "@@.\n@..\n.@@");
+    }
+
+    #[test]
+    fn test_removable_rolls_large_grid() {
+        // Given
+        let row = "@".repeat(1000);
+        let grid = parse_grid(vec![row.as_str(); 1000], &GridFormat::default()).unwrap();
+
+        // When
+        let result = grid.removable_rolls();
+
+        // Then - only the corners have fewer than 4 neighbours with rolls
+        assert_eq!(result.len(), 4);
+    }
+
+    static EXAMPLE: &str = "\
+..@@.@@@@.
+@@@.@.@.@@
+@@@@@.@.@@
+@.@@@@..@.
+@@.@@@@.@@
+.@@@@@@@.@
+.@.@.@.@@@
+@.@@@.@@@@
+.@@@@@@@@.
+@.@.@@@.@.";
+
+    // the generations found by removing every removable roll until none are left
+    fn repeated_removal(grid: &Grid) -> Vec<Vec<Coordinate>> {
+        let mut steps = vec![];
+        let mut current = grid.clone();
+        loop {
+            let removable = current.removable_rolls();
+            if removable.is_empty() {
+                return steps;
+            }
+            current = current.remove_rolls(&removable);
+            steps.push(removable);
+        }
+    }
+
+    // pseudo random values from a fixed seed, so generated grids are the same every run
+    fn seeded_values(seed: u64) -> impl Iterator<Item = u64> {
+        std::iter::successors(Some(seed), |seed| {
+            Some(seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407))
+        }).skip(1).map(|seed| seed >> 33)
+    }
+
+    fn seeded_rows(seed: u64, size: usize, symbols: &[char]) -> Vec<String> {
+        let mut values = seeded_values(seed);
+        (0..size).map(|_| (0..size).map(|_| {
+            symbols[(values.next().unwrap() % symbols.len() as u64) as usize]
+        }).collect()).collect()
+    }
+
+    fn rolls_at(steps: &[Vec<Coordinate>]) -> Vec<Vec<(u32, u32)>> {
+        steps.iter().map(|step| {
+            let mut positions: Vec<(u32, u32)> = step.iter().map(|c| (c.x, c.y)).collect();
+            positions.sort();
+            positions
+        }).collect()
+    }
+
+    #[test]
+    fn test_peel_generations_match_repeated_removal() {
+        // Given
+        let grid = parse_grid(EXAMPLE.lines().collect(), &GridFormat::default()).unwrap();
+        let expected = repeated_removal(&grid);
+
+        // When
+        let result = grid.peel_rolls(PeelMode::Generations);
+
+        // Then
+        assert_eq!(rolls_at(&result), rolls_at(&expected));
+    }
+
+    #[test]
+    fn test_peel_greedy_removes_one_roll_per_step() {
+        // Given
+        // @@@@@
+        // @@@@@
+        let grid = parse_grid(vec!["@@@@@", "@@@@@"], &GridFormat::default()).unwrap();
+
+        // When
+        let generations = grid.peel_rolls(PeelMode::Generations);
+        let greedy = grid.peel_rolls(PeelMode::Greedy);
+
+        // Then - the corners go first in both, but a generation waits for the whole grid
+        assert_eq!(rolls_at(&generations), vec![
+            vec![(0, 0), (0, 1), (4, 0), (4, 1)],
+            vec![(1, 0), (1, 1), (3, 0), (3, 1)],
+            vec![(2, 0), (2, 1)],
+        ]);
+        assert!(greedy.iter().all(|step| step.len() == 1));
+        assert_eq!(greedy.len(), 10);
+        assert_eq!(rolls_at(&[greedy[..4].concat()]), rolls_at(&generations[..1]));
+    }
+
+    // @@@@@
+    // @@@@@
+    // @@@@@
+    // @@@@@
+    // @@@@@
+    fn full_grid(rule: Rule) -> Grid {
+        parse_grid(vec!["@@@@@"; 5], &GridFormat::default()).unwrap().with_rule(rule)
+    }
+
+    fn neighbour_positions(grid: &Grid, x: u32, y: u32) -> Vec<(u32, u32)> {
+        let coord = grid.coords.iter().find(|c| c.x == x && c.y == y).unwrap();
+        let mut positions: Vec<(u32, u32)> = grid.neighbours(coord).iter().map(|c| (c.x, c.y)).collect();
+        positions.sort();
+        positions
+    }
+
+    #[test]
+    fn test_von_neumann_neighbourhood() {
+        // Given
+        let grid = full_grid(Rule{ neighbourhood: Neighbourhood::VonNeumann, ..Rule::default() });
+
+        // When
+        let centre = neighbour_positions(&grid, 2, 2);
+        let corner = neighbour_positions(&grid, 0, 0);
+
+        // Then
+        assert_eq!(centre, vec![(1, 2), (2, 1), (2, 3), (3, 2)]);
+        assert_eq!(corner, vec![(0, 1), (1, 0)]);
+        // Then - with 4 neighbours at most only the corners are below 3
+        let rule = Rule{ neighbourhood: Neighbourhood::VonNeumann, threshold: 3, ..Rule::default() };
+        assert_eq!(full_grid(rule).removable_rolls().len(), 4);
+    }
+
+    #[test]
+    fn test_moore_neighbourhood_with_radius() {
+        // Given
+        let grid = full_grid(Rule{ neighbourhood: Neighbourhood::Moore(2), ..Rule::default() });
+
+        // When
+        let centre = neighbour_positions(&grid, 2, 2);
+        let corner = neighbour_positions(&grid, 0, 0);
+
+        // Then
+        assert_eq!(centre.len(), 24);
+        assert_eq!(corner.len(), 8);
+        assert!(corner.iter().all(|(x, y)| *x <= 2 && *y <= 2));
+    }
+
+    #[test]
+    fn test_custom_neighbourhood() {
+        // Given - knight moves
+        let neighbourhood: Neighbourhood = "custom:1,2;2,1;2,-1;1,-2;-1,-2;-2,-1;-2,1;-1,2".parse().unwrap();
+        let grid = full_grid(Rule{ neighbourhood, ..Rule::default() });
+
+        // When
+        let centre = neighbour_positions(&grid, 2, 2);
+        let corner = neighbour_positions(&grid, 0, 0);
+
+        // Then
+        assert_eq!(centre, vec![(0, 1), (0, 3), (1, 0), (1, 4), (3, 0), (3, 4), (4, 1), (4, 3)]);
+        assert_eq!(corner, vec![(1, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn test_wrapping_neighbourhood() {
+        // Given
+        let grid = full_grid(Rule{ wrap: true, ..Rule::default() });
+
+        // When
+        let corner = neighbour_positions(&grid, 0, 0);
+
+        // Then
+        assert_eq!(corner, vec![(0, 1), (0, 4), (1, 0), (1, 1), (1, 4), (4, 0), (4, 1), (4, 4)]);
+        // Then - a full torus has no edges, so nothing can be removed
+        assert_eq!(grid.removable_rolls().len(), 0);
+        assert_eq!(grid.peel_rolls(PeelMode::Generations).len(), 0);
+    }
+
+    #[test]
+    fn test_wrapping_counts_each_neighbour_once() {
+        // Given
+        let grid = parse_grid(vec!["@@", "@@"], &GridFormat::default()).unwrap().with_rule(Rule{ wrap: true, ..Rule::default() });
+
+        // When
+        let corner = neighbour_positions(&grid, 0, 0);
+
+        // Then
+        assert_eq!(corner, vec![(0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_threshold_and_comparison() {
+        // Given
+        // .....
+        // ..@..
+        // .@@@.
+        // ..@..
+        // .....
+        let input = vec![".....", "..@..", ".@@@.", "..@..", "....."];
+        let rule = Rule{ threshold: 4, comparison: Comparison::GreaterOrEqual, ..Rule::default() };
+
+        // When
+        let result = parse_grid(input.clone(), &GridFormat::default()).unwrap().with_rule(rule).removable_rolls();
+
+        // Then - only the centre has 4 neighbours with rolls
+        assert_eq!(result.len(), 1);
+        assert_eq!((result[0].x, result[0].y), (2, 2));
+        let rule = Rule{ threshold: 3, comparison: Comparison::Equal, ..Rule::default() };
+        assert_eq!(parse_grid(input, &GridFormat::default()).unwrap().with_rule(rule).removable_rolls().len(), 4);
+    }
+
+    #[test]
+    fn test_peel_matches_repeated_removal_for_other_rules() {
+        // Given
+        let rows = seeded_rows(7, 12, &['.', '@', '@']);
+        let rules = vec![
+            Rule::default(),
+            Rule{ neighbourhood: Neighbourhood::VonNeumann, threshold: 2, ..Rule::default() },
+            Rule{ neighbourhood: Neighbourhood::Moore(2), threshold: 12, wrap: true, ..Rule::default() },
+            Rule{ neighbourhood: Neighbourhood::Custom(vec![(1, 0), (2, 0), (0, 1)]), threshold: 2, ..Rule::default() },
+            Rule{ threshold: 3, comparison: Comparison::Equal, ..Rule::default() },
+            Rule{ threshold: 5, comparison: Comparison::Greater, ..Rule::default() },
+        ];
+        for rule in rules {
+            let grid = parse_grid(rows.iter().map(|row| row.as_str()).collect(), &GridFormat::default()).unwrap().with_rule(rule.clone());
+            let expected = repeated_removal(&grid);
+
+            // When
+            let result = grid.peel_rolls(PeelMode::Generations);
+
+            // Then
+            assert_eq!(rolls_at(&result), rolls_at(&expected), "Failed for {:?}", rule);
+        }
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        // Given
+        let args: Vec<String> = ["--neighbourhood", "moore:2", "--threshold", "10", "--comparison", "le", "--wrap", "--greedy"]
+            .iter().map(|arg| arg.to_string()).collect();
+
+        // When
+        let options = Options::from_args(&args).unwrap();
+
+        // Then
+        assert_eq!(options.mode, PeelMode::Greedy);
+        assert_eq!(options.rule, Rule{ neighbourhood: Neighbourhood::Moore(2), threshold: 10, comparison: Comparison::LessOrEqual, wrap: true });
+        assert_eq!("hex".parse::<Neighbourhood>(), Err("Unknown neighbourhood"));
+        assert_eq!("custom:1".parse::<Neighbourhood>(), Err("Offset must be <dx>,<dy>"));
+        assert_eq!("moore:16".parse::<Neighbourhood>(), Ok(Neighbourhood::Moore(16)));
+        assert_eq!("moore:100000".parse::<Neighbourhood>(), Err("Moore radius is larger than 16"));
+    }
+
+    // ..@@.
+    // .@@@@
+    // ..@..
+    // with fewer than 3 neighbours only the roll on the right can go
+    fn small_generation_map() -> GenerationMap {
+        let options = Options{ rule: Rule{ threshold: 3, ..Rule::default() }, ..Options::default() };
+        generation_map("..@@.\n.@@@@\n..@..", &options).unwrap()
+    }
+
+    #[test]
+    fn test_generation_map_text() {
+        // When
+        let map = generation_map(EXAMPLE, &Options::default()).unwrap();
+
+        // Then
+        assert_eq!(map.to_text(), [
+            "..11.1121.",
+            "134.2.2.32",
+            "24578.1.33",
+            "2.69##..2.",
+            "13.####.21",
+            ".24#####.2",
+            ".2.#.#.##3",
+            "1.4##.###4",
+            ".23#####5.",
+            "1.1.###.1.",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_generation_map_formats() {
+        // Given
+        let map = small_generation_map();
+
+        // When
+        let text = map.to_text();
+        let csv = map.to_csv();
+        let pgm = map.to_pgm();
+        let ppm = map.to_ppm();
+
+        // Then
+        assert_eq!(text, "..##.\n.###1\n..#..\n");
+        assert_eq!(csv, "x,y,generation\n2,0,stable\n3,0,stable\n1,1,stable\n2,1,stable\n3,1,stable\n4,1,1\n2,2,stable\n");
+        assert!(pgm.starts_with(b"P5\n5 3\n255\n"));
+        assert_eq!(&pgm[11..], &[0, 0, 255, 255, 0, 0, 255, 255, 255, 40, 0, 0, 255, 0, 0]);
+        assert!(ppm.starts_with(b"P6\n5 3\n255\n"));
+        assert_eq!(&ppm[11..17], &[0, 0, 0, 0, 0, 0]);
+        assert_eq!(&ppm[17..20], &[255, 0, 0]);
+        assert_eq!(&ppm[38..41], &[40, 40, 40]);
+    }
+
+    #[test]
+    fn test_generation_levels_spread_over_generations() {
+        // Given
+        // @@@@@
+        // @@@@@
+        let grid = parse_grid(vec!["@@@@@", "@@@@@"], &GridFormat::default()).unwrap();
+        let map = GenerationMap::from_steps(&grid, &grid.peel_rolls(PeelMode::Generations));
+
+        // When
+        let levels: Vec<u8> = map.to_pgm()[11..16].to_vec();
+
+        // Then
+        assert_eq!(map.to_text(), "12321\n12321\n");
+        assert_eq!(levels, vec![40, 120, 200, 120, 40]);
+    }
+
+    // reads codes back out as a gif decoder would, so the encoder is checked against
+    // the decoding rules rather than against itself
+    fn lzw_decompress(bytes: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let initial: Vec<Vec<u8>> = (0..=end).map(|code| vec![code as u8]).collect();
+        let mut table = initial.clone();
+        let mut code_size = min_code_size as usize + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = vec![];
+        let mut bit = 0;
+        loop {
+            let code = (0..code_size).fold(0, |code, i| {
+                let position = bit + i;
+                code | (((bytes[position / 8] >> (position % 8)) & 1) as usize) << i
+            });
+            bit += code_size;
+            if code == clear {
+                table = initial.clone();
+                code_size = min_code_size as usize + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                break;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [previous.clone(), vec![previous[0]]].concat(),
+                (None, None) => panic!("Unknown code {}", code),
+            };
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    table.push([previous, vec![entry[0]]].concat());
+                }
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            output.extend(&entry);
+            previous = Some(entry);
+        }
+        output
+    }
+
+    #[test]
+    fn test_lzw_round_trips() {
+        // Given
+        let noise: Vec<u8> = seeded_values(3).take(50_000).map(|value| (value % 3) as u8).collect();
+        let cases = vec![vec![], vec![1], vec![0; 10_000], vec![0, 1, 2, 1, 0, 1, 2, 2, 2, 2, 1], noise];
+
+        for indices in cases {
+            // When
+            let compressed = lzw_compress(&indices, GIF_MIN_CODE_SIZE);
+            // Then
+            assert_eq!(lzw_decompress(&compressed, GIF_MIN_CODE_SIZE), indices);
+        }
+    }
+
+    fn frames(grid: &Grid, steps: &[Vec<Coordinate>]) -> Vec<Vec<u8>> {
+        let mut frames = vec![];
+        for_each_frame(grid, steps, |cells| {
+            frames.push(cells.to_vec());
+            Ok(())
+        }).unwrap();
+        frames
+    }
+
+    #[test]
+    fn test_frames_mark_rolls_about_to_go() {
+        // Given
+        // @@@
+        let grid = parse_grid(vec!["@@@"], &GridFormat::default()).unwrap().with_rule(Rule{ threshold: 2, ..Rule::default() });
+
+        // When
+        let result = frames(&grid, &grid.peel_rolls(PeelMode::Generations));
+
+        // Then
+        assert_eq!(result, vec![vec![REMOVING, ROLL, REMOVING], vec![FLOOR, REMOVING, FLOOR], vec![FLOOR; 3]]);
+    }
+
+    #[test]
+    fn test_render_frame_scales_cells() {
+        // Given
+        let style = FrameStyle{ cell_size: 2, floor: [0, 0, 0], roll: [255, 255, 255], removing: [255, 0, 0], fixed: [0, 0, 255] };
+        let cells = vec![ROLL, FLOOR, REMOVING, ROLL];
+
+        // When
+        let ppm = style.render(&cells, 2, 2, FrameFormat::Ppm);
+        let pgm = style.render(&cells, 2, 2, FrameFormat::Pgm);
+
+        // Then
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+        assert_eq!(&ppm[11..23], &[255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0]);
+        assert!(pgm.starts_with(b"P5\n4 4\n255\n"));
+        assert_eq!(&pgm[11..], &[255, 255, 0, 0, 255, 255, 0, 0, 76, 76, 255, 255, 76, 76, 255, 255]);
+    }
+
+    #[test]
+    fn test_gif_holds_every_frame() {
+        // Given
+        let grid = parse_grid(vec!["@@@@@", "@@@@@", "..@.."], &GridFormat::default()).unwrap();
+        let style = FrameStyle{ cell_size: 3, ..FrameStyle::default() };
+        let frames = frames(&grid, &grid.peel_rolls(PeelMode::Generations));
+
+        // When
+        let mut writer = GifWriter::new(vec![], &style, grid.width, grid.height, 20).unwrap();
+        frames.iter().for_each(|frame| writer.frame(frame).unwrap());
+        let gif = writer.finish().unwrap();
+
+        // Then
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(&gif[6..10], &[15, 0, 9, 0]);
+        assert_eq!(&gif[13..22], &[0x20, 0x20, 0x20, 0xd0, 0xd0, 0xd0, 0xe0, 0x40, 0x40]);
+        assert_eq!(gif.last(), Some(&0x3b));
+        // Then - walk each frame and decode its pixels
+        let mut position = 25 + 19;
+        let mut decoded = vec![];
+        while gif[position] == 0x21 {
+            position += 8;
+            assert_eq!(gif[position], 0x2c);
+            position += 10;
+            let min_code_size = gif[position];
+            position += 1;
+            let mut data = vec![];
+            while gif[position] != 0 {
+                let length = gif[position] as usize;
+                data.extend(&gif[position + 1..position + 1 + length]);
+                position += 1 + length;
+            }
+            position += 1;
+            decoded.push(lzw_decompress(&data, min_code_size));
+        }
+        let expected: Vec<Vec<u8>> = frames.iter().map(|frame| style.scale(frame, grid.width)).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_gif_rejects_oversized_images() {
+        // Given
+        let style = FrameStyle{ cell_size: 1000, ..FrameStyle::default() };
+
+        // When
+        let result = GifWriter::new(vec![], &style, 100, 1, 20);
+
+        // Then
+        assert_eq!(result.map(|writer| writer.out), Err("A 100000x1000 image is too large for a gif".to_string()));
+    }
+
+    #[test]
+    fn test_parse_colour() {
+        assert_eq!(parse_colour("#ff8000"), Ok([255, 128, 0]));
+        assert_eq!(parse_colour("0a0B0c"), Ok([10, 11, 12]));
+        assert_eq!(parse_colour("#fff"), Err("Colour must be six hex digits"));
+        assert_eq!(parse_colour("gg0000"), Err("Colour must be six hex digits"));
+    }
+
+    #[test]
+    fn test_parse_grid_rejects_bad_grids() {
+        let cases = vec![
+            (vec!["@@.", "@x.", "..@"], "Row 2, column 2: Unexpected character 'x'"),
+            (vec!["@@.", "@@", "..@"], "Row 2, column 3: Expected 3 cells but found 2"),
+            (vec!["@@.", "@@..", "..@"], "Row 2, column 4: Expected 3 cells but found 4"),
+            (vec!["@@.", "", "..@"], "Row 2, column 1: Expected 3 cells but found 0"),
+            (vec!["@@.", "@#.", "..@"], "Row 2, column 2: Unexpected character '#'"),
+        ];
+        for (rows, expected) in cases {
+            // When
+            let result = parse_grid(rows.clone(), &GridFormat::default()).map(|grid| grid.to_string());
+            // Then
+            assert_eq!(result, Err(expected.to_string()), "Failed for {:?}", rows);
+        }
+    }
+
+    #[test]
+    fn test_parse_grid_lenient_pads_to_longest_row() {
+        // Given
+        let format = GridFormat{ lenient: true, ..GridFormat::default() };
+
+        // When
+        let result = parse_grid(vec!["@", "@@@", ""], &format).unwrap();
+
+        // Then
+        assert_eq!((result.width, result.height), (3, 3));
+        assert_eq!(result.to_string(), "@..\n@@@\n...");
+        // Then - lenient mode still rejects unknown characters
+        assert_eq!(parse_grid(vec!["@", "@?"], &format).map(|grid| grid.width), Err("Row 2, column 2: Unexpected character '?'".to_string()));
+    }
+
+    #[test]
+    fn test_parse_grid_with_alphabet() {
+        // Given
+        let mut format = GridFormat::default();
+        format.kinds.set_alphabet("# ".parse().unwrap()).unwrap();
+
+        // When
+        let result = parse_grid(vec!["## ", " # "], &format).unwrap();
+
+        // Then - the grid is drawn back with the same alphabet
+        assert_eq!(result.to_string(), "## \n # ");
+        assert_eq!(result.removable_rolls().len(), 3);
+        assert_eq!(parse_grid(vec!["@@."], &format).map(|grid| grid.width), Err("Row 1, column 1: Unexpected character '@'".to_string()));
+        assert_eq!("@@".parse::<GridAlphabet>(), Err("Alphabet must be two different characters, a roll then floor"));
+        assert_eq!("@.x".parse::<GridAlphabet>(), Err("Alphabet must be two different characters, a roll then floor"));
+    }
+
+    #[test]
+    fn test_parts_report_parse_errors() {
+        // Given
+        let input = "..@\n.@\n";
+
+        // When
+        let result = part_one(input, &Options::default());
+
+        // Then
+        assert_eq!(result, Err("Row 2, column 3: Expected 3 cells but found 2".to_string()));
+        let lenient = Options{ grid_format: GridFormat{ lenient: true, ..GridFormat::default() }, ..Options::default() };
+        assert_eq!(part_two(input, &lenient), Ok(2));
+    }
+
+    fn warehouse_format() -> GridFormat {
+        GridFormat{ kinds: CellKinds::warehouse(), ..GridFormat::default() }
+    }
+
+    #[test]
+    fn test_warehouse_round_trips() {
+        // Given
+        let rows = vec!["#####", "#@P.#", "#R@@#", "#####"];
+
+        // When
+        let result = parse_grid(rows.clone(), &warehouse_format()).unwrap();
+
+        // Then
+        assert_eq!(result.to_string(), rows.join("\n"));
+        assert_eq!(result.coords[6].kind, CellKind::Roll);
+        assert_eq!(result.coords[7].kind, CellKind::Pallet);
+        assert_eq!(result.coords[11].kind, CellKind::Rack);
+        assert_eq!(parse_grid(rows, &GridFormat::default()).map(|grid| grid.width), Err("Row 1, column 1: Unexpected character '#'".to_string()));
+    }
+
+    #[test]
+    fn test_fixed_kinds_block_but_never_go() {
+        // Given
+        // #####
+        // #@@@#
+        // #####
+        let grid = parse_grid(vec!["#####", "#@@@#", "#####"], &warehouse_format()).unwrap();
+
+        // When
+        let removable = grid.removable_rolls();
+        let steps = grid.peel_rolls(PeelMode::Generations);
+
+        // Then - every roll is hemmed in by walls, and the walls themselves stay put
+        assert!(removable.is_empty());
+        assert!(steps.is_empty());
+        assert_eq!(GenerationMap::from_steps(&grid, &steps).to_text(), "=====\n=###=\n=====\n");
+    }
+
+    #[test]
+    fn test_kinds_that_do_not_occupy() {
+        // Given - racks that rolls can be reached through
+        let mut format = warehouse_format();
+        format.kinds.set("rack=R".parse().unwrap()).unwrap();
+        let grid = parse_grid(vec!["RRRRR", "R@@@R", "RRRRR"], &format).unwrap();
+
+        // When
+        let steps = grid.peel_rolls(PeelMode::Generations);
+
+        // Then
+        assert_eq!(rolls_at(&steps), vec![vec![(1, 1), (2, 1), (3, 1)]]);
+        let map = GenerationMap::from_steps(&grid, &steps);
+        assert_eq!(map.to_text(), ".....\n.111.\n.....\n");
+    }
+
+    #[test]
+    fn test_per_kind_thresholds() {
+        // Given - pallets need fewer neighbours than rolls to come away
+        let mut format = warehouse_format();
+        format.kinds.set("pallet=P,occupies,removable,threshold=2".parse().unwrap()).unwrap();
+        // @@@
+        // @P@
+        // @@@
+        let grid = parse_grid(vec!["@@@", "@P@", "@@@"], &format).unwrap();
+
+        // When
+        let map = GenerationMap::from_steps(&grid, &grid.peel_rolls(PeelMode::Generations));
+
+        // Then - the pallet has to wait until all but one of its neighbours are gone
+        assert_eq!(map.to_text(), "121\n232\n121\n");
+    }
+
+    #[test]
+    fn test_warehouse_peel_matches_repeated_removal() {
+        // Given
+        let rows = seeded_rows(11, 14, &['@', '@', '@', '.', 'P', '#', 'R']);
+        let mut format = warehouse_format();
+        format.kinds.set("pallet=P,occupies,removable,threshold=3".parse().unwrap()).unwrap();
+        format.kinds.set("rack=R,removable,threshold=1".parse().unwrap()).unwrap();
+        let grid = parse_grid(rows.iter().map(|row| row.as_str()).collect(), &format).unwrap();
+        let expected = repeated_removal(&grid);
+
+        // When
+        let result = grid.peel_rolls(PeelMode::Generations);
+
+        // Then
+        assert!(!expected.is_empty());
+        assert_eq!(rolls_at(&result), rolls_at(&expected));
+    }
+
+    #[test]
+    fn test_kind_spec_from_str() {
+        assert_eq!("wall=#,occupies".parse::<KindSpec>(),
+            Ok(KindSpec{ kind: CellKind::Wall, symbol: '#', occupies: true, removable: false, threshold: None }));
+        assert_eq!("pallet=P,occupies,removable,threshold=3".parse::<KindSpec>(),
+            Ok(KindSpec{ kind: CellKind::Pallet, symbol: 'P', occupies: true, removable: true, threshold: Some(3) }));
+        assert_eq!("crate=C".parse::<KindSpec>(), Err("Kind must be one of floor, roll, pallet, wall or rack".to_string()));
+        assert_eq!("wall".parse::<KindSpec>(), Err("Kind must start <kind>=<symbol>".to_string()));
+        assert_eq!("wall=##".parse::<KindSpec>(), Err("Symbol for wall must be a single character".to_string()));
+        assert_eq!("wall=#,heavy".parse::<KindSpec>(), Err("Unknown kind setting 'heavy'".to_string()));
+        assert_eq!(CellKinds::default().set("wall=@".parse().unwrap()), Err("Symbol '@' is already used for roll".to_string()));
+    }
 }
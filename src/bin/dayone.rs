@@ -1,45 +1,53 @@
-use std::{str::FromStr};
+use std::{collections::{BTreeMap, HashMap, VecDeque}, fmt::Display, fs::File, io::{self, BufRead, BufReader, Read, Write}, process::{Command, Stdio}, str::FromStr};
 
 static INPUT: &str = include_str!("../input/dayone.txt");
 
 // struct for dial
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 struct Dial {
-    current_position: i32,
-    zero_clicks: i32
+    current_position: i64,
+    zero_clicks: i64
 }
 
+const OVERFLOW: &str = "Dial arithmetic overflowed";
+
 // impl that applies an instruction
 impl Dial {
-    fn apply(&mut self, instruction: &Instruction) {
+    fn apply(&mut self, instruction: &Instruction) -> Result<(), &'static str> {
         match instruction {
             Instruction::RotateLeft { degree } => {
                 let old_position = self.current_position;
-                self.current_position = (self.current_position - degree).rem_euclid(100);
+                self.current_position = self.current_position.checked_sub(*degree)
+                    .ok_or(OVERFLOW)?
+                    .rem_euclid(100);
 
                 if old_position == 0 && *degree >= 100  {
-                    self.zero_clicks += degree.div_euclid(100);
+                    self.zero_clicks = self.zero_clicks.checked_add(degree.div_euclid(100))
+                        .ok_or(OVERFLOW)?;
                 } else if old_position != 0 && *degree >= old_position {
                     let remaining = degree - old_position;
-                    self.zero_clicks += remaining.div_euclid(100) + 1
-                }           
+                    self.zero_clicks = self.zero_clicks.checked_add(remaining.div_euclid(100) + 1)
+                        .ok_or(OVERFLOW)?;
+                }
             }
             Instruction::RotateRight { degree } => {
-                let new_position = self.current_position + degree;
+                let new_position = self.current_position.checked_add(*degree).ok_or(OVERFLOW)?;
                 self.current_position = new_position.rem_euclid(100);
 
                 let zero_passes = new_position.div_euclid(100);
-                self.zero_clicks += zero_passes.abs();
+                self.zero_clicks = self.zero_clicks.checked_add(zero_passes.abs())
+                    .ok_or(OVERFLOW)?;
             }
         }
+        Ok(())
     }
 }
 
 // enum for instruction
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Copy, Clone)]
 pub enum Instruction {
-    RotateLeft { degree: i32 },
-    RotateRight { degree: i32 },
+    RotateLeft { degree: i64 },
+    RotateRight { degree: i64 },
 }
 
 impl FromStr for Instruction {
@@ -49,7 +57,7 @@ impl FromStr for Instruction {
         let first = s.chars().next().ok_or("Empty string was passed")?;
         let tail = &s[first.len_utf8()..];
 
-        let degree: i32 = tail
+        let degree: i64 = tail
             .parse()
             .map_err(|_| "Failed to parse degree")?;
 
@@ -64,50 +72,418 @@ impl FromStr for Instruction {
     }
 }
 
-fn part_one() -> Result<(), String> {
-    let instructions: Vec<Instruction> = INPUT.lines()
-        .map(Instruction::from_str)
-        .collect::<Result<_, _>>()?;
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::RotateLeft { degree } => write!(f, "L{}", degree),
+            Instruction::RotateRight { degree } => write!(f, "R{}", degree),
+        }
+    }
+}
+
+type DialKey = (i64, i64);
+
+// breadth first search over (position, zero clicks) for the shortest sequence of
+// instructions taking the dial from start to target with exactly the required clicks.
+// adding 100 to a rotation lands in the same place with one more click, so only
+// rotations of 1 to 100 are searched and any clicks still missing at the target are
+// made up with whole turns on the last instruction
+fn solve(start: i64, target: i64, required_zero_clicks: i64, max_instructions: usize) -> Result<Option<Vec<Instruction>>, &'static str> {
+    let initial = Dial{ current_position: start, zero_clicks: 0 };
+    let is_solved = |dial: &Dial, depth: usize| dial.current_position == target
+        && (dial.zero_clicks == required_zero_clicks || (depth > 0 && dial.zero_clicks < required_zero_clicks));
+
+    let mut parents: HashMap<DialKey, Option<(DialKey, Instruction)>> = HashMap::new();
+    let mut queue: VecDeque<(Dial, usize)> = VecDeque::new();
+    parents.insert((initial.current_position, initial.zero_clicks), None);
+    queue.push_back((initial, 0));
+
+    let mut solved = is_solved(&initial, 0).then_some(initial);
+    while let Some((dial, depth)) = queue.pop_front() {
+        if solved.is_some() || depth == max_instructions {
+            break;
+        }
+
+        let candidates = (1..=100).flat_map(|degree| [
+            Instruction::RotateLeft { degree },
+            Instruction::RotateRight { degree },
+        ]);
+        for instruction in candidates {
+            let mut next = dial;
+            next.apply(&instruction)?;
+            let key = (next.current_position, next.zero_clicks);
+            if next.zero_clicks > required_zero_clicks || parents.contains_key(&key) {
+                continue;
+            }
+            parents.insert(key, Some(((dial.current_position, dial.zero_clicks), instruction)));
+            queue.push_back((next, depth + 1));
+            if is_solved(&next, depth + 1) {
+                solved = Some(next);
+                break;
+            }
+        }
+    }
+    let Some(dial) = solved else {
+        return Ok(None);
+    };
+
+    let mut instructions = vec![];
+    let mut key = (dial.current_position, dial.zero_clicks);
+    while let Some(Some((parent, instruction))) = parents.get(&key) {
+        instructions.push(*instruction);
+        key = *parent;
+    }
+    instructions.reverse();
+
+    let turns = required_zero_clicks - dial.zero_clicks;
+    if let Some(Instruction::RotateLeft { degree } | Instruction::RotateRight { degree }) = instructions.last_mut() {
+        *degree = turns.checked_mul(100)
+            .and_then(|extra| degree.checked_add(extra))
+            .ok_or("Too many zero clicks to search for")?;
+    }
+    Ok(Some(instructions))
+}
+
+fn run_solver(args: &[String]) -> Result<(), String> {
+    let [start, target, zero_clicks, max_instructions] = args else {
+        return Err("Usage: dayone --solve <start> <target> <zero clicks> <max instructions>".to_string());
+    };
+    let start: i64 = start.parse().map_err(|_| "Failed to parse start")?;
+    let target: i64 = target.parse().map_err(|_| "Failed to parse target")?;
+    if !(0..100).contains(&start) || !(0..100).contains(&target) {
+        return Err("Start and target must be dial positions from 0 to 99".to_string());
+    }
+    let zero_clicks: i64 = zero_clicks.parse().map_err(|_| "Failed to parse zero clicks")?;
+    if zero_clicks < 0 {
+        return Err("Zero clicks cannot be negative".to_string());
+    }
+    let max_instructions: usize = max_instructions.parse().map_err(|_| "Failed to parse max instructions")?;
+
+    match solve(start, target, zero_clicks, max_instructions)? {
+        Some(instructions) => {
+            println!("Solved in {} instructions:", instructions.len());
+            instructions.iter().for_each(|instruction| println!("{}", instruction));
+        }
+        None => println!("No sequence of at most {} instructions exists", max_instructions),
+    }
+    Ok(())
+}
+
+// both part answers, accumulated one instruction at a time
+#[derive(Debug, PartialEq)]
+struct Answers {
+    zero_landings: usize,
+    zero_clicks: i64
+}
+
+// folds instructions from any reader through the dial without holding them in memory
+fn process_instructions<R: BufRead>(mut reader: R, mut dial: Dial) -> Result<Answers, String> {
+    let mut zero_landings = 0;
+    let mut line = String::new();
+    let mut line_number = 0;
+
+    loop {
+        line.clear();
+        let bytes_read = reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        if bytes_read == 0 {
+            break;
+        }
+        line_number += 1;
+
+        Instruction::from_str(line.trim_end_matches(['\n', '\r']))
+            .and_then(|instruction| dial.apply(&instruction))
+            .map_err(|e| format!("Line {}: {}", line_number, e))?;
+        if dial.current_position == 0 {
+            zero_landings += 1;
+        }
+    }
+
+    Ok(Answers { zero_landings, zero_clicks: dial.zero_clicks })
+}
+
+fn run_streaming(args: &[String]) -> Result<(), String> {
+    let [path] = args else {
+        return Err("Usage: dayone --stream <path or - for stdin>".to_string());
+    };
     let dial = Dial{ current_position: 50, zero_clicks: 0 };
+    let answers = if path == "-" {
+        process_instructions(io::stdin().lock(), dial)?
+    } else {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        process_instructions(BufReader::new(file), dial)?
+    };
+
+    print_answers(&answers);
+    Ok(())
+}
 
+fn print_answers(answers: &Answers) {
+    println!("Position is 0 {} times", answers.zero_landings);
+    println!("Final zero clicks: {}", answers.zero_clicks);
+}
 
-    let dial_states: Vec<Dial> = instructions.iter().scan(dial, |state, instruction| {
+// the dial after each instruction in turn
+fn dial_states(instructions: &[Instruction], dial: Dial) -> Result<Vec<Dial>, &'static str> {
+    instructions.iter().scan(dial, |state, instruction| {
         let mut next_state = *state;
-        next_state.apply(instruction);
+        let applied = next_state.apply(instruction);
         *state = next_state;
-        Some(next_state)
-    }).collect();
+        Some(applied.map(|_| next_state))
+    }).collect()
+}
 
-    let zeros = dial_states.iter().filter(|d| d.current_position == 0).count();
-    println!("Position is 0 {} times", zeros);
+const RING_RADIUS: f64 = 10.0;
 
-    println!("Final dial position: {}", dial.current_position);
-    Ok(())
+// draws the 100 dial positions as a ring with zero at the top and the pointer as '*'
+fn render_ring(position: i64) -> String {
+    let height = 2 * RING_RADIUS as usize + 1;
+    let width = 2 * height;
+    let mut canvas = vec![vec![' '; width]; height];
+
+    let to_cell = |p: i64| {
+        let angle = p as f64 / 100.0 * std::f64::consts::TAU;
+        let x = (RING_RADIUS + RING_RADIUS * angle.sin()) * 2.0;
+        let y = RING_RADIUS - RING_RADIUS * angle.cos();
+        (x.round() as usize, y.round() as usize)
+    };
+
+    for p in 0..100 {
+        let (x, y) = to_cell(p);
+        canvas[y][x] = if p % 25 == 0 { '+' } else { '.' };
+    }
+    let (x, y) = to_cell(position);
+    canvas[y][x] = '*';
+
+    canvas.iter()
+        .map(|row| row.iter().collect::<String>().trim_end().to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// sets the terminal to deliver single key presses without echo, restoring it on drop
+struct RawTerminal;
+
+const CTRL_C: u8 = 0x03;
+
+impl RawTerminal {
+    fn enable() -> Result<RawTerminal, String> {
+        // signals are turned off too, so ctrl-c arrives as a key and the terminal is
+        // always restored on the way out
+        let status = Command::new("stty").args(["-icanon", "-echo", "-isig"]).stdin(Stdio::inherit()).status()
+            .map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err("The debugger needs an interactive terminal".to_string());
+        }
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = Command::new("stty").args(["icanon", "echo", "isig"]).stdin(Stdio::inherit()).status();
+    }
 }
 
-fn part_two() -> Result<(), String> {
+fn render_step(instructions: &[Instruction], states: &[Dial], step: usize) -> String {
+    let dial = states[step];
+    let mut frame = render_ring(dial.current_position);
+    frame.push_str(&format!("\n\nStep {}/{}", step, instructions.len()));
+    if step > 0 {
+        let clicks = dial.zero_clicks - states[step - 1].zero_clicks;
+        let summary = format!("  {}  zero clicks +{}", instructions[step - 1], clicks);
+        if clicks > 0 {
+            // passing through zero is shown in bold red
+            frame.push_str(&format!("\x1b[1;31m{}\x1b[0m", summary));
+        } else {
+            frame.push_str(&summary);
+        }
+    }
+    frame.push_str(&format!("\nPosition: {}  Zero clicks: {}", dial.current_position, dial.zero_clicks));
+    frame.push_str("\n\n[n] next  [p] previous  [g<number><enter>] jump  [q] quit");
+    frame
+}
+
+fn run_debugger() -> Result<(), String> {
     let instructions: Vec<Instruction> = INPUT.lines()
         .map(Instruction::from_str)
         .collect::<Result<_, _>>()?;
     let dial = Dial{ current_position: 50, zero_clicks: 0 };
-    
-    let dial_states: Vec<Dial> = instructions.iter().scan(dial, |state, instruction| {
-        let mut next_state = *state;
-        next_state.apply(instruction);
-        *state = next_state;
-        Some(next_state)
-    }).collect();
+    let states: Vec<Dial> = std::iter::once(dial).chain(dial_states(&instructions, dial)?).collect();
+
+    let _terminal = RawTerminal::enable()?;
+    let mut keys = io::stdin().lock().bytes();
+    let mut step = 0;
+
+    'debugger: loop {
+        print!("\x1b[2J\x1b[H{}", render_step(&instructions, &states, step));
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        match keys.next() {
+            Some(Ok(b'n')) => step = (step + 1).min(instructions.len()),
+            Some(Ok(b'p')) => step = step.saturating_sub(1),
+            Some(Ok(b'g')) => {
+                let mut digits = String::new();
+                print!("\nJump to: ");
+                io::stdout().flush().map_err(|e| e.to_string())?;
+                while let Some(Ok(key)) = keys.next() {
+                    match key {
+                        b'0'..=b'9' => {
+                            digits.push(key as char);
+                            print!("{}", key as char);
+                            io::stdout().flush().map_err(|e| e.to_string())?;
+                        }
+                        CTRL_C => break 'debugger,
+                        _ => break,
+                    }
+                }
+                if let Ok(target) = digits.parse::<usize>() {
+                    step = target.min(instructions.len());
+                }
+            }
+            Some(Ok(b'q' | CTRL_C)) | None => break,
+            Some(Ok(_)) => {}
+            Some(Err(e)) => return Err(e.to_string()),
+        }
+    }
+    println!();
+    Ok(())
+}
 
+const MOST_FREQUENT_POSITIONS: usize = 5;
+
+// summary of a whole run, built from the dial after each instruction
+#[derive(Debug, PartialEq)]
+struct DialStatistics {
+    position_histogram: BTreeMap<i64, usize>,
+    longest_streak_without_zero: usize,
+    distance_left: i128,
+    distance_right: i128,
+    most_frequent_positions: Vec<(i64, usize)>,
+    most_zero_clicks: Option<(usize, Instruction, i64)>
+}
 
-    let final_state = dial_states.last().unwrap();
+impl DialStatistics {
+    fn from_states(instructions: &[Instruction], initial: Dial, states: &[Dial]) -> DialStatistics {
+        let mut position_histogram = BTreeMap::new();
+        states.iter().for_each(|dial| *position_histogram.entry(dial.current_position).or_insert(0) += 1);
+
+        let mut most_frequent_positions: Vec<(i64, usize)> = position_histogram.iter()
+            .map(|(position, count)| (*position, *count))
+            .collect();
+        most_frequent_positions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        most_frequent_positions.truncate(MOST_FREQUENT_POSITIONS);
+
+        let clicks_per_instruction: Vec<i64> = std::iter::once(&initial).chain(states.iter())
+            .zip(states.iter())
+            .map(|(before, after)| after.zero_clicks - before.zero_clicks)
+            .collect();
+
+        let (longest_streak_without_zero, _) = clicks_per_instruction.iter()
+            .fold((0, 0), |(longest, current), clicks| {
+                let current = if *clicks == 0 { current + 1 } else { 0 };
+                (longest.max(current), current)
+            });
+
+        let most_zero_clicks = clicks_per_instruction.iter()
+            .enumerate()
+            .filter(|(_, clicks)| **clicks > 0)
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0)))
+            .map(|(index, clicks)| (index + 1, instructions[index], *clicks));
+
+        let (distance_left, distance_right) = instructions.iter()
+            .fold((0, 0), |(left, right), instruction| match instruction {
+                Instruction::RotateLeft { degree } => (left + *degree as i128, right),
+                Instruction::RotateRight { degree } => (left, right + *degree as i128),
+            });
+
+        DialStatistics {
+            position_histogram,
+            longest_streak_without_zero,
+            distance_left,
+            distance_right,
+            most_frequent_positions,
+            most_zero_clicks
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let histogram = self.position_histogram.iter()
+            .map(|(position, count)| format!("\"{}\":{}", position, count))
+            .collect::<Vec<String>>()
+            .join(",");
+        let most_frequent = self.most_frequent_positions.iter()
+            .map(|(position, count)| format!("{{\"position\":{},\"count\":{}}}", position, count))
+            .collect::<Vec<String>>()
+            .join(",");
+        let most_zero_clicks = match self.most_zero_clicks {
+            Some((number, instruction, clicks)) =>
+                format!("{{\"instruction_number\":{},\"instruction\":\"{}\",\"zero_clicks\":{}}}", number, instruction, clicks),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"position_histogram\":{{{}}},\"longest_streak_without_zero\":{},\"distance_left\":{},\"distance_right\":{},\"most_frequent_positions\":[{}],\"most_zero_clicks\":{}}}",
+            histogram, self.longest_streak_without_zero, self.distance_left, self.distance_right, most_frequent, most_zero_clicks
+        )
+    }
+}
+
+impl Display for DialStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<32} {:>12}", "Longest streak without zero", self.longest_streak_without_zero)?;
+        writeln!(f, "{:<32} {:>12}", "Total distance left", self.distance_left)?;
+        writeln!(f, "{:<32} {:>12}", "Total distance right", self.distance_right)?;
+        match self.most_zero_clicks {
+            Some((number, instruction, clicks)) =>
+                writeln!(f, "{:<32} {:>12}", "Most zero clicks", format!("#{} {} ({})", number, instruction, clicks))?,
+            None => writeln!(f, "{:<32} {:>12}", "Most zero clicks", "-")?,
+        }
+        writeln!(f)?;
+        writeln!(f, "{:<10} {:>8}", "Position", "Landings")?;
+        for (position, count) in &self.most_frequent_positions {
+            writeln!(f, "{:<10} {:>8}", position, count)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Histogram")?;
+        for (position, count) in &self.position_histogram {
+            writeln!(f, "{:>3} {:>8}", position, count)?;
+        }
+        Ok(())
+    }
+}
+
+fn run_statistics(args: &[String]) -> Result<(), String> {
+    let instructions: Vec<Instruction> = INPUT.lines()
+        .map(Instruction::from_str)
+        .collect::<Result<_, _>>()?;
+    let dial = Dial{ current_position: 50, zero_clicks: 0 };
+    let dial_states = dial_states(&instructions, dial)?;
 
-    println!("Final zero clicks: {}", final_state.zero_clicks);
+    let statistics = DialStatistics::from_states(&instructions, dial, &dial_states);
+    match args {
+        [] => print!("{}", statistics),
+        [format] if format == "--json" => println!("{}", statistics.to_json()),
+        _ => return Err("Usage: dayone --stats [--json]".to_string()),
+    }
     Ok(())
 }
 
 fn main() -> Result<(), String> {
-    let _ = part_one();
-    part_two()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "--solve") {
+        return run_solver(&args[1..]);
+    }
+    if args.first().is_some_and(|arg| arg == "--debug") {
+        return run_debugger();
+    }
+    if args.first().is_some_and(|arg| arg == "--stats") {
+        return run_statistics(&args[1..]);
+    }
+    if args.first().is_some_and(|arg| arg == "--stream") {
+        return run_streaming(&args[1..]);
+    }
+    let dial = Dial{ current_position: 50, zero_clicks: 0 };
+    print_answers(&process_instructions(INPUT.as_bytes(), dial)?);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -168,7 +544,7 @@ mod tests {
         let instruction = Instruction::RotateLeft { degree: 45 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 5);
@@ -182,7 +558,7 @@ mod tests {
         let instruction = Instruction::RotateLeft { degree: 45 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 55);
@@ -196,7 +572,7 @@ mod tests {
         let instruction = Instruction::RotateLeft { degree: 350 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 0);
@@ -210,7 +586,7 @@ mod tests {
         let instruction = Instruction::RotateLeft { degree: 355 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 95);
@@ -224,7 +600,7 @@ mod tests {
         let instruction = Instruction::RotateLeft { degree: 55 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 95);
@@ -238,7 +614,7 @@ mod tests {
         let instruction = Instruction::RotateLeft { degree: 150 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 0);
@@ -252,7 +628,7 @@ mod tests {
         let instruction = Instruction::RotateLeft { degree: 200 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 0);
@@ -266,7 +642,7 @@ mod tests {
         let instruction = Instruction::RotateRight{ degree: 355 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 5);
@@ -280,7 +656,7 @@ mod tests {
         let instruction = Instruction::RotateRight{ degree: 350 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 0);
@@ -294,7 +670,7 @@ mod tests {
         let instruction = Instruction::RotateRight{ degree: 350 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 50);
@@ -308,11 +684,265 @@ mod tests {
         let instruction = Instruction::RotateRight{ degree: 400 };
 
         // When
-        dial.apply(&instruction);
+        dial.apply(&instruction).unwrap();
         
         // Then
         assert_eq!(dial.current_position, 0);
         assert_eq!(dial.zero_clicks, 4);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_solve_returns_empty_sequence_when_already_solved() {
+        // Given
+        // When
+        let actual = solve(50, 50, 0, 3);
+
+        // Then
+        assert_eq!(actual, Ok(Some(vec![])));
+    }
+
+    #[test]
+    fn test_solve_finds_single_instruction() {
+        // Given
+        // When
+        let actual = solve(50, 0, 1, 3).unwrap().unwrap();
+
+        // Then
+        assert_eq!(actual.len(), 1);
+        let mut dial = Dial { current_position: 50, zero_clicks: 0 };
+        actual.iter().for_each(|instruction| dial.apply(instruction).unwrap());
+        assert_eq!(dial, Dial { current_position: 0, zero_clicks: 1 });
+    }
+
+    #[test]
+    fn test_solve_finds_shortest_sequence_for_many_zero_clicks() {
+        // Given
+        // When
+        let actual = solve(10, 20, 3, 3).unwrap().unwrap();
+
+        // Then
+        let mut dial = Dial { current_position: 10, zero_clicks: 0 };
+        actual.iter().for_each(|instruction| dial.apply(instruction).unwrap());
+        assert_eq!(dial, Dial { current_position: 20, zero_clicks: 3 });
+        assert_eq!(actual.len(), 1);
+    }
+
+    #[test]
+    fn test_solve_makes_up_many_zero_clicks_with_whole_turns() {
+        for required in [120, 1_000_000] {
+            // Given
+            // When
+            let actual = solve(50, 7, required, 3).unwrap().unwrap();
+
+            // Then
+            let mut dial = Dial { current_position: 50, zero_clicks: 0 };
+            actual.iter().for_each(|instruction| dial.apply(instruction).unwrap());
+            assert_eq!(dial, Dial { current_position: 7, zero_clicks: required });
+            assert_eq!(actual.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_solve_returns_none_when_no_sequence_within_limit() {
+        // Given
+        // landing on zero always clicks, so no sequence of any length works
+        // When
+        let actual = solve(50, 0, 0, 5);
+
+        // Then
+        assert_eq!(actual, Ok(None));
+    }
+
+    #[test]
+    fn test_solve_rejects_unsearchable_zero_clicks() {
+        // Given
+        // When
+        let actual = solve(50, 0, i64::MAX, 5);
+
+        // Then
+        assert_eq!(actual, Err("Too many zero clicks to search for"));
+        let args: Vec<String> = ["50", "0", "-1", "5"].iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(run_solver(&args), Err("Zero clicks cannot be negative".to_string()));
+    }
+
+    #[test]
+    fn test_run_solver_rejects_positions_off_the_dial() {
+        for (start, target) in [("50", "150"), ("-1", "0"), ("100", "0"), ("0", "-50")] {
+            // Given
+            let args: Vec<String> = [start, target, "1", "5"].iter().map(|arg| arg.to_string()).collect();
+            // When
+            let actual = run_solver(&args);
+            // Then
+            assert_eq!(actual, Err("Start and target must be dial positions from 0 to 99".to_string()), "Failed for {} {}", start, target);
+        }
+    }
+
+    #[test]
+    fn test_process_instructions_gives_both_answers() {
+        // Given
+        let input = "L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82\n";
+        let dial = Dial { current_position: 50, zero_clicks: 0 };
+
+        // When
+        let actual = process_instructions(input.as_bytes(), dial);
+
+        // Then
+        assert_eq!(actual, Ok(Answers { zero_landings: 3, zero_clicks: 6 }));
+    }
+
+    #[test]
+    fn test_process_instructions_reports_line_of_bad_instruction() {
+        // Given
+        let input = "L68\r\nX30\r\n";
+        let dial = Dial { current_position: 50, zero_clicks: 0 };
+
+        // When
+        let actual = process_instructions(input.as_bytes(), dial);
+
+        // Then
+        assert_eq!(actual, Err("Line 2: Unexpected instruction character".to_string()));
+    }
+
+    #[test]
+    fn test_dial_states_has_state_after_each_instruction() {
+        // Given
+        let instructions = vec![
+            Instruction::RotateLeft { degree: 50 },
+            Instruction::RotateRight { degree: 25 },
+        ];
+        let dial = Dial { current_position: 50, zero_clicks: 0 };
+
+        // When
+        let actual = dial_states(&instructions, dial);
+
+        // Then
+        assert_eq!(actual, Ok(vec![
+            Dial { current_position: 0, zero_clicks: 1 },
+            Dial { current_position: 25, zero_clicks: 1 },
+        ]));
+    }
+
+    #[test]
+    fn test_render_ring_places_pointer() {
+        // Given
+        // When
+        let at_zero = render_ring(0);
+        let at_fifty = render_ring(50);
+
+        // Then - zero is at the top, fifty at the bottom
+        assert!(at_zero.lines().next().unwrap().contains('*'));
+        assert!(at_fifty.lines().last().unwrap().contains('*'));
+        assert_eq!(at_zero.matches('*').count(), 1);
+    }
+
+    #[test]
+    fn test_apply_handles_rotations_beyond_i32_max() {
+        // Given
+        let mut dial = Dial { current_position: 50, zero_clicks: 0 };
+        let instruction = Instruction::RotateRight { degree: i32::MAX as i64 };
+
+        // When
+        dial.apply(&instruction).unwrap();
+        dial.apply(&instruction).unwrap();
+
+        // Then - 50 + 2 * 2147483647 = 4294967344
+        assert_eq!(dial.current_position, 44);
+        assert_eq!(dial.zero_clicks, 42949673);
+    }
+
+    #[test]
+    fn test_apply_handles_left_rotation_of_i32_max() {
+        // Given
+        let mut dial = Dial { current_position: 50, zero_clicks: 0 };
+        let instruction = Instruction::RotateLeft { degree: i32::MAX as i64 };
+
+        // When
+        dial.apply(&instruction).unwrap();
+
+        // Then - 50 - 2147483647 = -2147483597
+        assert_eq!(dial.current_position, 3);
+        assert_eq!(dial.zero_clicks, 21474836);
+    }
+
+    #[test]
+    fn test_instruction_from_str_parses_degree_beyond_i32_max() {
+        // Given
+        let input = "R2147483648";
+
+        // When
+        let actual = Instruction::from_str(input);
+
+        // Then
+        assert_eq!(actual, Ok(Instruction::RotateRight { degree: i32::MAX as i64 + 1 }));
+    }
+
+    #[test]
+    fn test_apply_errors_when_position_overflows() {
+        // Given
+        let mut dial = Dial { current_position: 50, zero_clicks: 0 };
+        let instruction = Instruction::RotateRight { degree: i64::MAX };
+
+        // When
+        let actual = dial.apply(&instruction);
+
+        // Then
+        assert_eq!(actual, Err(OVERFLOW));
+    }
+
+    #[test]
+    fn test_apply_errors_when_zero_clicks_overflow() {
+        // Given
+        let mut dial = Dial { current_position: 0, zero_clicks: i64::MAX };
+        let instruction = Instruction::RotateLeft { degree: 100 };
+
+        // When
+        let actual = dial.apply(&instruction);
+
+        // Then
+        assert_eq!(actual, Err(OVERFLOW));
+    }
+
+    #[test]
+    fn test_dial_statistics_from_states() {
+        // Given
+        let instructions = vec![
+            Instruction::RotateLeft { degree: 50 },
+            Instruction::RotateRight { degree: 10 },
+            Instruction::RotateRight { degree: 10 },
+            Instruction::RotateLeft { degree: 220 },
+            Instruction::RotateRight { degree: 10 },
+        ];
+        let dial = Dial { current_position: 50, zero_clicks: 0 };
+        let states = dial_states(&instructions, dial).unwrap();
+
+        // When
+        let actual = DialStatistics::from_states(&instructions, dial, &states);
+
+        // Then - positions visited are 0, 10, 20, 0, 10
+        assert_eq!(actual.position_histogram, BTreeMap::from([(0, 2), (10, 2), (20, 1)]));
+        assert_eq!(actual.most_frequent_positions, vec![(0, 2), (10, 2), (20, 1)]);
+        assert_eq!(actual.longest_streak_without_zero, 2);
+        assert_eq!(actual.distance_left, 270);
+        assert_eq!(actual.distance_right, 30);
+        assert_eq!(actual.most_zero_clicks, Some((4, Instruction::RotateLeft { degree: 220 }, 3)));
+    }
+
+    #[test]
+    fn test_dial_statistics_to_json() {
+        // Given
+        let instructions = vec![Instruction::RotateRight { degree: 150 }];
+        let dial = Dial { current_position: 50, zero_clicks: 0 };
+        let states = dial_states(&instructions, dial).unwrap();
+
+        // When
+        let actual = DialStatistics::from_states(&instructions, dial, &states).to_json();
+
+        // Then
+        assert_eq!(actual, concat!(
+            "{\"position_histogram\":{\"0\":1},\"longest_streak_without_zero\":0,",
+            "\"distance_left\":0,\"distance_right\":150,",
+            "\"most_frequent_positions\":[{\"position\":0,\"count\":1}],",
+            "\"most_zero_clicks\":{\"instruction_number\":1,\"instruction\":\"R150\",\"zero_clicks\":2}}"
+        ));
+    }
+}
@@ -1,4 +1,5 @@
-use std::fmt::Display;
+use num_bigint::BigUint;
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, str::FromStr};
 
 
 static INPUT: &str = include_str!("../input/daythree.txt");
@@ -8,49 +9,302 @@ struct Bank {
     batteries: String,
 }
 
-fn find_largest_first_place_of_n_digit_number_in_string(n: usize, string: String) -> usize {
-    let len = string.len();
-    let (head, _) = string.split_at(len - n + 1);
+impl FromStr for Bank {
+    type Err = String;
 
-    let first_battery_value = head.chars().filter_map(|c| c.to_digit(10)).max();
-    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("Empty bank".to_string());
+        }
+        if let Some((column, c)) = s.chars().enumerate().find(|(_, c)| !c.is_ascii_digit()) {
+            return Err(format!("Column {}: Unexpected character '{}'", column + 1, c));
+        }
+        Ok(Bank{ batteries: s.to_string() })
+    }
+}
+
+// one bank per line, each with at least k batteries to switch on
+fn parse_banks(input: &str, k: usize) -> Result<Vec<Bank>, String> {
+    input.lines().enumerate().map(|(i, line)| {
+        let line_number = i + 1;
+        let bank = Bank::from_str(line).map_err(|e| format!("Line {}: {}", line_number, e))?;
+        if bank.batteries.len() < k {
+            return Err(format!("Line {}: Bank of {} batteries is shorter than {}", line_number, bank.batteries.len(), k));
+        }
+        Ok(bank)
+    }).collect()
+}
 
-    head.chars()
-        .position(|c| c.to_digit(10) == first_battery_value)
-        .unwrap()
+// an arbitrary precision joltage, so it can hold any number of batteries and any sum of them
+type Joltage = BigUint;
+
+// the batteries switched on in a bank, as their positions in the bank, the digits
+// read in order and their value
+#[derive(Debug, Clone, PartialEq)]
+struct Selection {
+    positions: Vec<usize>,
+    digits: String,
+    joltage: Joltage
 }
 
 impl Bank {
-    fn two_digit_joltage(&self) -> u64 {
+    // the largest number made by switching on k batteries, keeping their order. a digit
+    // can replace smaller digits chosen before it while enough batteries remain after it
+    fn max_joltage(&self, k: usize) -> Result<Selection, String> {
+        let len = self.batteries.len();
+        if k > len {
+            return Err(format!("Cannot switch on {} batteries in a bank of {}", k, len));
+        }
+
+        let batteries = self.batteries.as_bytes();
+        let mut drops_left = len - k;
+        let mut positions: Vec<usize> = Vec::with_capacity(len);
+        for (position, digit) in batteries.iter().enumerate() {
+            while drops_left > 0 && positions.last().is_some_and(|last| batteries[*last] < *digit) {
+                positions.pop();
+                drops_left -= 1;
+            }
+            positions.push(position);
+        }
+        positions.truncate(k);
+
+        self.selection_at(positions)
+    }
+
+    fn selection_at(&self, positions: Vec<usize>) -> Result<Selection, String> {
+        let batteries = self.batteries.as_bytes();
+        let digits: String = positions.iter().map(|position| batteries[*position] as char).collect();
+        let joltage = Joltage::parse_bytes(digits.as_bytes(), 10).unwrap_or_default();
+        Ok(Selection{ positions, digits, joltage })
+    }
+
+    // the best k batteries under wiring constraints, found by dynamic programming over
+    // (position, batteries still to choose, gap state, window state). every selection has
+    // k digits, so comparing digit sequences compares joltages
+    fn select(&self, k: usize, objective: Objective, constraints: &Constraints) -> Result<Selection, String> {
         let len = self.batteries.len();
-        let (head, _) = self.batteries.split_at(len - 1);
+        if k > len {
+            return Err(format!("Cannot switch on {} batteries in a bank of {}", k, len));
+        }
+        if let Some(index) = constraints.must_include && index >= len {
+            return Err(format!("Battery {} is not in a bank of {}", index, len));
+        }
+        if let Some(window) = constraints.window {
+            if window.size == 0 {
+                return Err("Window size must be at least 1".to_string());
+            }
+            if window.states() > MAX_WINDOW_STATES {
+                return Err(format!("A window of {} with a limit of {} has too many states to search", window.size, window.limit));
+            }
+        }
+
+        let solver = Solver{ batteries: self.batteries.as_bytes(), objective, constraints };
+        let positions = solver.solve(k).ok_or("No selection satisfies the constraints")?;
+        self.selection_at(positions)
+    }
+}
 
-        let first_battery_value = head.chars().filter_map(|c| c.to_digit(10)).max();
-        let first_battery_position = head.chars()
-            .position(|c| c.to_digit(10) == first_battery_value)
-            .unwrap();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Objective {
+    Max,
+    Min,
+}
 
-        let (_, remaining) = self.batteries.split_at(first_battery_position + 1);
+// at most `limit` batteries switched on among any `size` neighbouring batteries
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Window {
+    size: usize,
+    limit: usize
+}
 
-        let second_battery_value = remaining.chars().filter_map(|c| c.to_digit(10)).max();
-        let second_batter_position = remaining.chars()
-            .position(|c| c.to_digit(10) == second_battery_value)
-            .unwrap();
+// the search keeps a table entry per position, batteries left to choose and window state,
+// so windows are capped by how many states they have rather than by their size
+const MAX_WINDOW_STATES: usize = 1000;
 
-        let largest_joltage_string = head.chars().nth(first_battery_position).unwrap().to_string() + &remaining.chars().nth(second_batter_position).unwrap().to_string();
-        largest_joltage_string.parse::<u64>().unwrap()
+impl Window {
+    // the ways to choose at most limit of the previous size - 1 batteries, counted up to
+    // just past the cap
+    fn states(&self) -> usize {
+        let previous = self.size.saturating_sub(1);
+        let mut binomial: usize = 1;
+        let mut states: usize = 1;
+        for chosen in 0..self.limit.min(previous) {
+            binomial = binomial.saturating_mul(previous - chosen) / (chosen + 1);
+            states = states.saturating_add(binomial);
+            if states > MAX_WINDOW_STATES {
+                break;
+            }
+        }
+        states
     }
+}
 
-    fn twelve_digit_joltage(&self) -> u64 { 
-        let mut remaining = self.batteries.as_str();
-        let joltage: String = (0..12).map( |i| {
-            let index_next_battery = find_largest_first_place_of_n_digit_number_in_string(12 - i, remaining.to_string());
-            let next_battery_value = remaining.chars().nth(index_next_battery).unwrap();
-            remaining = &remaining[index_next_battery + 1..];
-            next_battery_value
-        }).collect();
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Constraints {
+    max_gap: Option<usize>,
+    must_include: Option<usize>,
+    window: Option<Window>
+}
 
-        joltage.parse::<u64>().unwrap()
+impl Constraints {
+    fn is_empty(&self) -> bool {
+        *self == Constraints::default()
+    }
+}
+
+// what is known before deciding on a battery: how many are still to choose, the last
+// chosen position and the chosen positions still inside the window. the window never
+// holds more than its limit, so there are at most (size choose limit) window states
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SolverState {
+    remaining: usize,
+    last: Option<usize>,
+    window: Vec<usize>
+}
+
+// the best decision from a state, pointing at the state it leads to in the next layer
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Done,
+    Take(usize),
+    // also keeps where the next battery is taken, so reading digits never walks the skips
+    Skip(usize, Option<(usize, usize)>),
+}
+
+// the states reachable before a position, with the best step from each, or None when
+// no selection can be finished from it
+#[derive(Default)]
+struct Layer {
+    index: HashMap<SolverState, usize>,
+    states: Vec<SolverState>,
+    steps: Vec<Option<Step>>
+}
+
+impl Layer {
+    fn insert(&mut self, state: SolverState) {
+        if !self.index.contains_key(&state) {
+            self.index.insert(state.clone(), self.states.len());
+            self.states.push(state);
+        }
+    }
+}
+
+struct Solver<'a> {
+    batteries: &'a [u8],
+    objective: Objective,
+    constraints: &'a Constraints
+}
+
+impl Solver<'_> {
+    // finds every state reachable from the start, then the best step from each state
+    // working back from the end of the bank, then follows the steps from the start
+    fn solve(&self, k: usize) -> Option<Vec<usize>> {
+        let len = self.batteries.len();
+        let mut layers: Vec<Layer> = (0..=len).map(|_| Layer::default()).collect();
+        layers[0].insert(SolverState{ remaining: k, last: None, window: vec![] });
+        for position in 0..len {
+            let (reached, next) = layers.split_at_mut(position + 1);
+            for state in &reached[position].states {
+                self.successors(position, state).into_iter().flatten().for_each(|state| next[0].insert(state));
+            }
+        }
+        for position in (0..=len).rev() {
+            let steps = layers[position].states.iter().map(|state| self.best_step(&layers, position, state)).collect();
+            layers[position].steps = steps;
+        }
+
+        let mut positions = vec![];
+        let mut index = 0;
+        for (position, layer) in layers.iter().enumerate() {
+            match layer.steps[index]? {
+                Step::Done => return Some(positions),
+                Step::Take(next) => {
+                    positions.push(position);
+                    index = next;
+                }
+                Step::Skip(next, _) => index = next,
+            }
+        }
+        None
+    }
+
+    // the states after taking and after skipping the battery at position, where the
+    // constraints allow it and enough batteries are left to finish
+    fn successors(&self, position: usize, state: &SolverState) -> [Option<SolverState>; 2] {
+        let constraints = self.constraints;
+        if state.remaining == 0 || position >= self.batteries.len() {
+            return [None, None];
+        }
+        let left = self.batteries.len() - position - 1;
+
+        // the chosen positions still inside the window before the next position
+        let slide = |taken: Option<usize>| match constraints.window {
+            None => vec![],
+            Some(window) => state.window.iter().copied().chain(taken)
+                .filter(|chosen| chosen + window.size > position + 1)
+                .collect(),
+        };
+
+        let can_take = constraints.window.is_none_or(|window| state.window.len() < window.limit)
+            && constraints.max_gap.is_none_or(|gap| state.last.is_none_or(|last| position - last <= gap));
+        let take = can_take.then(|| SolverState{
+            remaining: state.remaining - 1,
+            last: constraints.max_gap.map(|_| position),
+            window: slide(Some(position))
+        });
+
+        let gap_exhausted = constraints.max_gap
+            .is_some_and(|gap| state.last.is_some_and(|last| position + 1 - last > gap));
+        let must_take = constraints.must_include == Some(position);
+        let skip = (!gap_exhausted && !must_take).then(|| SolverState{ window: slide(None), ..state.clone() });
+
+        [take.filter(|next| next.remaining <= left), skip.filter(|next| next.remaining <= left)]
+    }
+
+    // every later layer already has its steps
+    fn best_step(&self, layers: &[Layer], position: usize, state: &SolverState) -> Option<Step> {
+        if state.remaining == 0 {
+            let missed_include = self.constraints.must_include.is_some_and(|index| index >= position);
+            return if missed_include { None } else { Some(Step::Done) };
+        }
+        let finishes = |next: Option<SolverState>| next
+            .and_then(|next| layers[position + 1].index.get(&next).copied())
+            .filter(|index| layers[position + 1].steps[*index].is_some());
+        let [take, skip] = self.successors(position, state).map(finishes);
+
+        let prefer_take = match (take, skip) {
+            (Some(take), Some(skip)) => {
+                let taken = std::iter::once(self.batteries[position]).chain(self.digits(layers, position + 1, take));
+                let ordering = taken.cmp(self.digits(layers, position + 1, skip));
+                match self.objective {
+                    Objective::Max => ordering != Ordering::Less,
+                    Objective::Min => ordering != Ordering::Greater,
+                }
+            }
+            (take, _) => take.is_some(),
+        };
+        match (take, skip) {
+            (Some(take), _) if prefer_take => Some(Step::Take(take)),
+            (_, Some(skip)) => Some(Step::Skip(skip, next_take(layers, position + 1, skip))),
+            _ => None,
+        }
+    }
+
+    // the digits chosen by following the steps from a state
+    fn digits<'a>(&'a self, layers: &'a [Layer], position: usize, index: usize) -> impl Iterator<Item = u8> + 'a {
+        std::iter::successors(next_take(layers, position, index), |(position, index)| match layers[*position].steps[*index] {
+            Some(Step::Take(next)) => next_take(layers, position + 1, next),
+            _ => None,
+        }).map(|(position, _)| self.batteries[position])
+    }
+}
+
+// the position and state where the next battery is taken following the steps from a state
+fn next_take(layers: &[Layer], position: usize, index: usize) -> Option<(usize, usize)> {
+    match layers[position].steps[index] {
+        Some(Step::Take(_)) => Some((position, index)),
+        Some(Step::Skip(_, next)) => next,
+        _ => None,
     }
 }
 
@@ -60,26 +314,101 @@ impl Display for Bank {
     }
 }
 
+// a bank with the switched on batteries shown in bold green
+struct Highlighted<'a> {
+    bank: &'a Bank,
+    selection: &'a Selection
+}
+
+impl Display for Highlighted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (position, battery) in self.bank.batteries.chars().enumerate() {
+            if self.selection.positions.binary_search(&position).is_ok() {
+                write!(f, "\x1b[1;32m{}\x1b[0m", battery)?;
+            } else {
+                write!(f, "{}", battery)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn render_selections(banks: &[Bank], selections: &[Selection]) {
+    for (bank, selection) in banks.iter().zip(selections) {
+        println!("{}  {}  {:?}", Highlighted{ bank, selection }, selection.joltage, selection.positions);
+    }
+}
+
+
+struct Options {
+    render: bool,
+    objective: Objective,
+    constraints: Constraints
+}
+
+fn parse_number(arg: Option<&String>, flag: &str) -> Result<usize, String> {
+    let value = arg.ok_or(format!("Missing number after {}", flag))?;
+    value.parse().map_err(|_| format!("Invalid number '{}' after {}", value, flag))
+}
+
+impl Options {
+    fn from_args(args: &[String]) -> Result<Options, String> {
+        let mut options = Options{ render: false, objective: Objective::Max, constraints: Constraints::default() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--render" => options.render = true,
+                "--min" => options.objective = Objective::Min,
+                "--max-gap" => options.constraints.max_gap = Some(parse_number(args.next(), arg)?),
+                "--include" => options.constraints.must_include = Some(parse_number(args.next(), arg)?),
+                "--window" => {
+                    let size = parse_number(args.next(), arg)?;
+                    let limit = parse_number(args.next(), arg)?;
+                    options.constraints.window = Some(Window{ size, limit });
+                }
+                _ => return Err(format!("Unexpected argument: {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+
+    // the plain puzzle keeps to the linear time selection
+    fn select(&self, bank: &Bank, k: usize) -> Result<Selection, String> {
+        if self.objective == Objective::Max && self.constraints.is_empty() {
+            bank.max_joltage(k)
+        } else {
+            bank.select(k, self.objective, &self.constraints)
+        }
+    }
+}
 
-fn part_one() -> Result<(), String> {
-    let lines: Vec<&str> = INPUT.lines().collect();
-    let banks: Vec<Bank> = lines.iter().map(|line| Bank{ batteries: line.to_string() }).collect();
-    let joltage_sum: u64 = banks.iter().map(Bank::two_digit_joltage).sum();
+fn part_one(options: &Options) -> Result<(), String> {
+    let banks = parse_banks(INPUT, 2)?;
+    let selections = banks.iter().map(|bank| options.select(bank, 2)).collect::<Result<Vec<Selection>, String>>()?;
+    if options.render {
+        render_selections(&banks, &selections);
+    }
+    let joltage_sum: Joltage = selections.iter().map(|selection| &selection.joltage).sum();
     println!("Sum of joltage: {}", joltage_sum);
     Ok(())
 }
 
-fn part_two() -> Result<(), String> {
-    let lines: Vec<&str> = INPUT.lines().collect();
-    let banks: Vec<Bank> = lines.iter().map(|line| Bank{ batteries: line.to_string() }).collect();
-    let joltage_sum: u64 = banks.iter().map(Bank::twelve_digit_joltage).sum();
+fn part_two(options: &Options) -> Result<(), String> {
+    let banks = parse_banks(INPUT, 12)?;
+    let selections = banks.iter().map(|bank| options.select(bank, 12)).collect::<Result<Vec<Selection>, String>>()?;
+    if options.render {
+        render_selections(&banks, &selections);
+    }
+    let joltage_sum: Joltage = selections.iter().map(|selection| &selection.joltage).sum();
     println!("Sum of joltage: {}", joltage_sum);
     Ok(())
 }
 
-fn main(){
-    let _ = part_one();
-    let _ = part_two();
+fn main() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = Options::from_args(&args)?;
+    part_one(&options)?;
+    part_two(&options)
 }
 
 #[cfg(test)]
@@ -90,7 +419,7 @@ mod tests {
     #[test]
     fn test_bank_two_digit_joltage() {
         // Given
-        let test_cases = vec![
+        let test_cases: Vec<(Bank, u64)> = vec![
             (Bank{ batteries: "987654321111111".to_string() }, 98),
             (Bank{ batteries: "811111111111119".to_string() }, 89),
             (Bank{ batteries: "234234234234278".to_string() }, 78),
@@ -99,16 +428,16 @@ mod tests {
 
         for (input, expected) in test_cases {
             // When
-            let actual = input.two_digit_joltage();
+            let actual = input.max_joltage(2).map(|selection| selection.joltage);
             // Then
-            assert_eq!(actual, expected, "Failed for input: {}", input)
+            assert_eq!(actual, Ok(Joltage::from(expected)), "Failed for input: {}", input)
         }
     }
 
     #[test]
     fn test_bank_twelve_digit_joltage() {
         // Given
-        let test_cases = vec![
+        let test_cases: Vec<(Bank, u64)> = vec![
             (Bank{ batteries: "987654321111111".to_string() }, 987654321111),
             (Bank{ batteries: "811111111111119".to_string() }, 811111111119),
             (Bank{ batteries: "234234234234278".to_string() }, 434234234278),
@@ -117,9 +446,337 @@ mod tests {
 
         for (input, expected) in test_cases {
             // When
-            let actual = input.twelve_digit_joltage();
+            let actual = input.max_joltage(12).map(|selection| selection.joltage);
             // Then
-            assert_eq!(actual, expected, "Failed for input: {}", input)
+            assert_eq!(actual, Ok(Joltage::from(expected)), "Failed for input: {}", input)
         }
     }
-}
\ No newline at end of file
+
+    fn brute_force_max_joltage(batteries: &str, k: usize) -> u64 {
+        let digits: Vec<u64> = batteries.bytes().map(|b| (b - b'0') as u64).collect();
+        (0u32..1 << digits.len())
+            .filter(|mask| mask.count_ones() as usize == k)
+            .map(|mask| (0..digits.len())
+                .filter(|i| mask & (1 << i) != 0)
+                .fold(0, |acc, i| acc * 10 + digits[i]))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_max_joltage_matches_brute_force_for_every_short_bank() {
+        // Given - every bank of up to 6 batteries using the digits 1 to 4
+        let banks = (1..=6).flat_map(|len| {
+            (0..4usize.pow(len)).map(move |n| {
+                (0..len).map(|i| char::from(b'1' + (n / 4usize.pow(i) % 4) as u8)).collect::<String>()
+            })
+        });
+
+        for batteries in banks {
+            for k in 0..=batteries.len() {
+                // When
+                let actual = Bank{ batteries: batteries.clone() }.max_joltage(k).map(|selection| selection.joltage);
+                // Then
+                assert_eq!(actual, Ok(Joltage::from(brute_force_max_joltage(&batteries, k))), "Failed for {} with k = {}", batteries, k);
+            }
+        }
+    }
+
+    // pseudo random values from a fixed seed, so generated banks are the same every run
+    fn seeded_random(seed: u64) -> impl FnMut() -> usize {
+        let mut seed = seed;
+        move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as usize
+        }
+    }
+
+    #[test]
+    fn test_max_joltage_matches_brute_force_for_random_banks() {
+        // Given - pseudo random banks of up to 14 batteries from a fixed seed
+        let mut next = seeded_random(2025);
+
+        for _ in 0..200 {
+            let len = 1 + next() % 14;
+            let batteries: String = (0..len).map(|_| char::from(b'0' + (next() % 10) as u8)).collect();
+            let k = next() % (len + 1);
+            // When
+            let actual = Bank{ batteries: batteries.clone() }.max_joltage(k).map(|selection| selection.joltage);
+            // Then
+            assert_eq!(actual, Ok(Joltage::from(brute_force_max_joltage(&batteries, k))), "Failed for {} with k = {}", batteries, k);
+        }
+    }
+
+    #[test]
+    fn test_max_joltage_errors_when_k_exceeds_bank() {
+        // Given
+        let bank = Bank{ batteries: "12345".to_string() };
+
+        // When
+        let actual = bank.max_joltage(6);
+
+        // Then
+        assert_eq!(actual, Err("Cannot switch on 6 batteries in a bank of 5".to_string()));
+    }
+
+    #[test]
+    fn test_max_joltage_beyond_u64() {
+        // Given
+        let bank = Bank{ batteries: "1234567890".repeat(5) };
+
+        // When
+        let actual = bank.max_joltage(25).unwrap();
+
+        // Then
+        assert_eq!(actual.digits, "9989012345678901234567890");
+        assert_eq!(actual.joltage.to_string(), "9989012345678901234567890");
+    }
+
+    #[test]
+    fn test_max_joltage_keeps_leading_zero_digits() {
+        // Given
+        let bank = Bank{ batteries: "0001".to_string() };
+
+        // When
+        let actual = bank.max_joltage(3).unwrap();
+
+        // Then
+        assert_eq!(actual.digits, "001");
+        assert_eq!(actual.joltage, Joltage::from(1u64));
+    }
+
+    #[test]
+    fn test_joltage_sum_does_not_overflow() {
+        // Given
+        let joltages = [Joltage::from(u64::MAX), Joltage::from(u64::MAX), Joltage::from_str("9999999999999999999").unwrap()];
+
+        // When
+        let actual: Joltage = joltages.iter().sum();
+
+        // Then
+        assert_eq!(actual.to_string(), "46893488147419103229");
+    }
+
+    #[test]
+    fn test_max_joltage_reports_positions() {
+        // Given
+        let test_cases = vec![
+            (Bank{ batteries: "811111111111119".to_string() }, 2, vec![0, 14]),
+            (Bank{ batteries: "818181911112111".to_string() }, 2, vec![6, 11]),
+            (Bank{ batteries: "234234234234278".to_string() }, 12, vec![2, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]),
+        ];
+
+        for (input, k, expected) in test_cases {
+            // When
+            let actual = input.max_joltage(k).unwrap();
+            // Then
+            assert_eq!(actual.positions, expected, "Failed for input: {}", input);
+            let digits: String = actual.positions.iter().map(|p| input.batteries.as_bytes()[*p] as char).collect();
+            assert_eq!(actual.digits, digits, "Failed for input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_highlighted_bank() {
+        // Given
+        let bank = Bank{ batteries: "8119".to_string() };
+        let selection = bank.max_joltage(2).unwrap();
+
+        // When
+        let actual = Highlighted{ bank: &bank, selection: &selection }.to_string();
+
+        // Then
+        assert_eq!(actual, "\x1b[1;32m8\x1b[0m11\x1b[1;32m9\x1b[0m");
+    }
+
+    // whether positions, in increasing order, can all be switched on together
+    fn allows(constraints: &Constraints, positions: &[usize]) -> bool {
+        let gaps_ok = constraints.max_gap.is_none_or(|gap| positions.windows(2).all(|pair| pair[1] - pair[0] <= gap));
+        let include_ok = constraints.must_include.is_none_or(|index| positions.contains(&index));
+        let window_ok = constraints.window.is_none_or(|Window{ size, limit }| {
+            positions.iter().enumerate().all(|(i, start)| {
+                positions[i..].iter().take_while(|position| **position < start + size).count() <= limit
+            })
+        });
+        gaps_ok && include_ok && window_ok
+    }
+
+    // the best digits over every subset of k positions the constraints allow
+    fn brute_force_select(batteries: &str, k: usize, objective: Objective, constraints: &Constraints) -> Option<String> {
+        let digits = batteries.as_bytes();
+        let candidates = (0u32..1 << digits.len())
+            .filter(|mask| mask.count_ones() as usize == k)
+            .map(|mask| (0..digits.len()).filter(|i| mask & (1 << i) != 0).collect::<Vec<usize>>())
+            .filter(|positions| allows(constraints, positions))
+            .map(|positions| positions.iter().map(|p| digits[*p] as char).collect::<String>());
+        match objective {
+            Objective::Max => candidates.max(),
+            Objective::Min => candidates.min(),
+        }
+    }
+
+    fn random_banks() -> Vec<(String, usize, usize)> {
+        let mut next = seeded_random(42);
+        (0..150).map(|_| {
+            let len = 1 + next() % 12;
+            let batteries: String = (0..len).map(|_| char::from(b'0' + (next() % 10) as u8)).collect();
+            let k = next() % (len + 1);
+            let parameter = next() % (len + 1);
+            (batteries, k, parameter)
+        }).collect()
+    }
+
+    fn assert_matches_brute_force(batteries: &str, k: usize, objective: Objective, constraints: Constraints) {
+        let bank = Bank{ batteries: batteries.to_string() };
+        let actual = bank.select(k, objective, &constraints).ok();
+        let expected = brute_force_select(batteries, k, objective, &constraints);
+        assert_eq!(actual.as_ref().map(|selection| selection.digits.clone()), expected,
+            "Failed for {} with k = {}, {:?}, {:?}", batteries, k, objective, constraints);
+        if let Some(selection) = actual {
+            assert!(allows(&constraints, &selection.positions), "Positions break the constraints for {}", batteries);
+        }
+    }
+
+    #[test]
+    fn test_min_joltage() {
+        // Given
+        let bank = Bank{ batteries: "818181911112111".to_string() };
+
+        // When
+        let actual = bank.select(4, Objective::Min, &Constraints::default()).unwrap();
+
+        // Then
+        assert_eq!(actual.digits, "1111");
+        assert_eq!(actual.joltage, Joltage::from(1111u64));
+    }
+
+    #[test]
+    fn test_unconstrained_select_matches_max_joltage() {
+        for (batteries, k, _) in random_banks() {
+            // Given
+            let bank = Bank{ batteries };
+            // When
+            let actual = bank.select(k, Objective::Max, &Constraints::default());
+            // Then
+            assert_eq!(actual, bank.max_joltage(k), "Failed for {} with k = {}", bank, k);
+        }
+    }
+
+    #[test]
+    fn test_min_joltage_matches_brute_force() {
+        for (batteries, k, _) in random_banks() {
+            assert_matches_brute_force(&batteries, k, Objective::Min, Constraints::default());
+        }
+    }
+
+    #[test]
+    fn test_max_joltage_with_gap_matches_brute_force() {
+        for (batteries, k, gap) in random_banks() {
+            assert_matches_brute_force(&batteries, k, Objective::Max, Constraints{ max_gap: Some(gap), ..Constraints::default() });
+        }
+    }
+
+    #[test]
+    fn test_max_joltage_including_matches_brute_force() {
+        for (batteries, k, index) in random_banks() {
+            let index = index.min(batteries.len() - 1);
+            assert_matches_brute_force(&batteries, k, Objective::Max, Constraints{ must_include: Some(index), ..Constraints::default() });
+        }
+    }
+
+    #[test]
+    fn test_max_joltage_in_windows_matches_brute_force() {
+        for (batteries, k, size) in random_banks() {
+            let window = Window{ size: size.max(1), limit: k % 3 + 1 };
+            assert_matches_brute_force(&batteries, k, Objective::Max, Constraints{ window: Some(window), ..Constraints::default() });
+        }
+    }
+
+    #[test]
+    fn test_constrained_selections() {
+        // Given
+        let bank = Bank{ batteries: "9191".to_string() };
+        let gap = Constraints{ max_gap: Some(1), ..Constraints::default() };
+        let no_gap = Constraints{ max_gap: Some(0), ..Constraints::default() };
+        let include = Constraints{ must_include: Some(1), ..Constraints::default() };
+        let window = Constraints{ window: Some(Window{ size: 4, limit: 1 }), ..Constraints::default() };
+        let missing = Constraints{ must_include: Some(4), ..Constraints::default() };
+        let empty_window = Constraints{ window: Some(Window{ size: 0, limit: 1 }), ..Constraints::default() };
+        let wide_window = Constraints{ window: Some(Window{ size: 24, limit: 5 }), ..Constraints::default() };
+        let endless_window = Constraints{ window: Some(Window{ size: usize::MAX, limit: usize::MAX }), ..Constraints::default() };
+
+        // When
+        // Then
+        assert_eq!(bank.select(2, Objective::Max, &gap).unwrap().positions, vec![0, 1]);
+        assert_eq!(bank.select(2, Objective::Max, &no_gap), Err("No selection satisfies the constraints".to_string()));
+        assert_eq!(bank.select(1, Objective::Max, &no_gap).unwrap().positions, vec![0]);
+        assert_eq!(bank.select(2, Objective::Max, &include).unwrap().positions, vec![0, 1]);
+        assert_eq!(bank.select(2, Objective::Max, &window), Err("No selection satisfies the constraints".to_string()));
+        assert_eq!(bank.select(2, Objective::Max, &missing), Err("Battery 4 is not in a bank of 4".to_string()));
+        assert_eq!(bank.select(2, Objective::Max, &empty_window), Err("Window size must be at least 1".to_string()));
+        assert_eq!(bank.select(2, Objective::Max, &wide_window), Err("A window of 24 with a limit of 5 has too many states to search".to_string()));
+        assert!(bank.select(2, Objective::Max, &endless_window).is_err());
+    }
+
+    #[test]
+    fn test_constrained_selection_in_a_long_bank() {
+        // Given
+        let bank = Bank{ batteries: "2".repeat(4990) + &"1".repeat(10) };
+        let gap = Constraints{ max_gap: Some(1), must_include: Some(4995), ..Constraints::default() };
+
+        // When
+        let min = bank.select(12, Objective::Min, &Constraints::default()).unwrap();
+        let max = bank.select(12, Objective::Max, &gap).unwrap();
+
+        // Then
+        assert_eq!(min.digits, "221111111111");
+        assert_eq!(max.digits, "222222111111");
+        assert_eq!(max.positions, (4984..4996).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_parse_banks() {
+        // Given
+        let input = "987654321111111\n811111111111119\n";
+
+        // When
+        let actual = parse_banks(input, 12);
+
+        // Then
+        assert_eq!(actual, Ok(vec![
+            Bank{ batteries: "987654321111111".to_string() },
+            Bank{ batteries: "811111111111119".to_string() },
+        ]));
+    }
+
+    #[test]
+    fn test_bank_from_str_rejects_bad_banks() {
+        let cases = vec![
+            ("", "Empty bank"),
+            ("12a4", "Column 3: Unexpected character 'a'"),
+            ("-123", "Column 1: Unexpected character '-'"),
+            ("12 3", "Column 3: Unexpected character ' '"),
+        ];
+        for (line, expected) in cases {
+            // When
+            let actual = Bank::from_str(line);
+            // Then
+            assert_eq!(actual, Err(expected.to_string()), "Failed for {:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_parse_banks_reports_line() {
+        let cases = vec![
+            ("1234\n\n5678", 2, "Line 2: Empty bank"),
+            ("1234\n56x8", 2, "Line 2: Column 3: Unexpected character 'x'"),
+            ("987654321111111\n12345", 12, "Line 2: Bank of 5 batteries is shorter than 12"),
+        ];
+        for (input, k, expected) in cases {
+            // When
+            let actual = parse_banks(input, k);
+            // Then
+            assert_eq!(actual, Err(expected.to_string()), "Failed for {:?}", input);
+        }
+    }
+}
@@ -1,129 +1,667 @@
-use std::{fmt::Display, str::FromStr};
+use num_bigint::BigUint;
+use std::{collections::BTreeMap, fmt::Display, fmt::Write, num::{IntErrorKind, ParseIntError}, str::FromStr};
 
 static INPUT: &str = include_str!("../input/daytwo.txt");
 
+// the rules behind each part of the puzzle, in the rule expression syntax
+const PART_ONE_RULE: &str = "repeats(2)";
+const PART_TWO_RULE: &str = "repeats_at_least(2)";
+
+// rules only compare digits with each other, so they see an id as its digit values
+// and behave the same in any radix
 trait ProductIdValidator {
-    fn is_valid_product_id_by_one_repetition(&self) -> bool;
-    fn is_valid_product_id_by_many_repetitions(&self) -> bool;
-}
-
-impl ProductIdValidator for String {
-    fn is_valid_product_id_by_one_repetition(&self) -> bool {
-        let len = self.len();
-        if len % 2 == 1 {
-            return true;
-        }
-        let midpoint = len / 2;
-        let (left, right) = self.split_at(midpoint);
-        left.ne(right)
-    }
-    
-    fn is_valid_product_id_by_many_repetitions(&self) -> bool {
-        let len = self.len();
-        let midpoint = len / 2;
-        for i in 1..=midpoint {
-            let (head, tail) = self.split_at(i);
-            if tail == head {
-                return false;
-            }
-            let mut acc = tail;
-            while !acc.is_empty() {
-                if acc.starts_with(head) {
-                    acc = acc.strip_prefix(head).unwrap();
-                } else {
-                    break;
-                }
+    fn is_invalid(&self, id: &[u8]) -> bool;
+
+    fn is_valid(&self, id: &[u8]) -> bool {
+        !self.is_invalid(id)
+    }
+}
+
+// whether the id is a block of `period` digits repeated end to end
+fn is_periodic(id: &[u8], period: usize) -> bool {
+    let len = id.len();
+    period > 0 && period < len && len.is_multiple_of(period) && id[..len - period] == id[period..]
+}
+
+// a rule flags ids as invalid, and rules compose with and, or and not
+#[derive(Debug, Clone, PartialEq)]
+enum Rule {
+    Repeats(usize),
+    RepeatsAtLeast(usize),
+    BlockAtLeast(usize),
+    Palindrome,
+    Run(usize),
+    And(Box<Rule>, Box<Rule>),
+    Or(Box<Rule>, Box<Rule>),
+    Not(Box<Rule>),
+}
+
+impl ProductIdValidator for Rule {
+    fn is_invalid(&self, id: &[u8]) -> bool {
+        let len = id.len();
+        match self {
+            Rule::Repeats(times) => len.is_multiple_of(*times) && is_periodic(id, len / times),
+            Rule::RepeatsAtLeast(times) => (1..=len / times).any(|period| is_periodic(id, period)),
+            Rule::BlockAtLeast(length) => (*length..len).any(|period| is_periodic(id, period)),
+            Rule::Palindrome => id.iter().eq(id.iter().rev()),
+            Rule::Run(length) => id
+                .chunk_by(|a, b| a == b)
+                .any(|run| run.len() >= *length),
+            Rule::And(left, right) => left.is_invalid(id) && right.is_invalid(id),
+            Rule::Or(left, right) => left.is_invalid(id) || right.is_invalid(id),
+            Rule::Not(rule) => !rule.is_invalid(id),
+        }
+    }
+}
+
+impl Rule {
+    // the block lengths an invalid id of `digits` digits can be built from, or None when
+    // the rule is not purely about repetition and ids have to be checked one by one
+    fn periods(&self, digits: u32) -> Option<Vec<u32>> {
+        let divisors = (1..digits).filter(move |period| digits.is_multiple_of(*period));
+        match self {
+            Rule::Repeats(times) => Some(divisors.filter(|period| *period as u64 * *times as u64 == digits as u64).collect()),
+            Rule::RepeatsAtLeast(times) => Some(divisors.filter(|period| digits / period >= *times as u32).collect()),
+            Rule::BlockAtLeast(length) => Some(divisors.filter(|period| *period >= *length as u32).collect()),
+            Rule::Or(left, right) => {
+                let mut periods = left.periods(digits)?;
+                periods.extend(right.periods(digits)?);
+                periods.sort();
+                periods.dedup();
+                Some(periods)
             }
-            if acc.is_empty() {
-                return false
+            _ => None,
+        }
+    }
+
+    // whether invalid ids can be generated from block lengths rather than scanned
+    fn is_repetition(&self) -> bool {
+        self.periods(1).is_some()
+    }
+
+    // the repeating block that makes an id invalid and how many times it repeats, preferring
+    // the shortest block the rule allows, or the shortest block of any kind otherwise
+    fn repeating_block<'a>(&self, id: &'a [u8]) -> Option<(&'a [u8], usize)> {
+        let len = id.len();
+        let periods: Vec<usize> = match self.periods(len as u32) {
+            Some(periods) => periods.iter().map(|period| *period as usize).collect(),
+            None => (1..len).collect(),
+        };
+        periods.into_iter()
+            .find(|period| is_periodic(id, *period))
+            .map(|period| (&id[..period], len / period))
+    }
+}
+
+// recursive descent over the rule expression grammar:
+//   expr   := term ('|' term)*
+//   term   := factor ('&' factor)*
+//   factor := '!' factor | '(' expr ')' | name ['(' number ')']
+struct RuleParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl RuleParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&expected).is_some()
+    }
+
+    fn expr(&mut self) -> Result<Rule, &'static str> {
+        let mut rule = self.term()?;
+        while self.eat('|') {
+            rule = Rule::Or(Box::new(rule), Box::new(self.term()?));
+        }
+        Ok(rule)
+    }
+
+    fn term(&mut self) -> Result<Rule, &'static str> {
+        let mut rule = self.factor()?;
+        while self.eat('&') {
+            rule = Rule::And(Box::new(rule), Box::new(self.factor()?));
+        }
+        Ok(rule)
+    }
+
+    fn factor(&mut self) -> Result<Rule, &'static str> {
+        if self.eat('!') {
+            return Ok(Rule::Not(Box::new(self.factor()?)));
+        }
+        if self.eat('(') {
+            let rule = self.expr()?;
+            return if self.eat(')') { Ok(rule) } else { Err("Missing ')'") };
+        }
+
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_lowercase() || *c == '_') {
+            name.push(c);
+        }
+        match name.as_str() {
+            "repeats" => Ok(Rule::Repeats(self.argument(2)?)),
+            "repeats_at_least" => Ok(Rule::RepeatsAtLeast(self.argument(2)?)),
+            "block_at_least" => Ok(Rule::BlockAtLeast(self.argument(1)?)),
+            "run" => Ok(Rule::Run(self.argument(1)?)),
+            "palindrome" => Ok(Rule::Palindrome),
+            "" => Err("Expected a rule"),
+            _ => Err("Unknown rule"),
+        }
+    }
+
+    fn argument(&mut self, minimum: usize) -> Result<usize, &'static str> {
+        if !self.eat('(') {
+            return Err("Missing '(' after rule name");
+        }
+        self.skip_whitespace();
+        let mut digits = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit()) {
+            digits.push(c);
+        }
+        let value: usize = digits.parse().map_err(|e: ParseIntError| match e.kind() {
+            IntErrorKind::PosOverflow => "Rule argument is too large",
+            _ => "Rule argument is not a number",
+        })?;
+        if value < minimum {
+            return Err("Rule argument is too small");
+        }
+        if value > u32::MAX as usize {
+            return Err("Rule argument is too large");
+        }
+        if !self.eat(')') {
+            return Err("Missing ')'");
+        }
+        Ok(value)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = RuleParser{ chars: s.chars().peekable() };
+        let rule = parser.expr()?;
+        parser.skip_whitespace();
+        match parser.chars.peek() {
+            None => Ok(rule),
+            Some(_) => Err("Unexpected trailing characters in rule"),
+        }
+    }
+}
+
+const STANDARD_SYMBOLS: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+// the symbols used to write product ids, in order of digit value
+#[derive(Debug, Clone, PartialEq)]
+struct Alphabet {
+    symbols: Vec<char>
+}
+
+impl Alphabet {
+    fn with_radix(radix: usize) -> Result<Alphabet, &'static str> {
+        if !(2..=STANDARD_SYMBOLS.len()).contains(&radix) {
+            return Err("Radix must be between 2 and 36");
+        }
+        Ok(Alphabet{ symbols: STANDARD_SYMBOLS.chars().take(radix).collect() })
+    }
+
+    fn radix(&self) -> u8 {
+        self.symbols.len() as u8
+    }
+
+    fn digit_value(&self, symbol: char) -> Option<u8> {
+        self.symbols.iter().position(|c| *c == symbol)
+            .or_else(|| self.symbols.iter().position(|c| *c == symbol.to_ascii_lowercase()))
+            .map(|position| position as u8)
+    }
+
+    fn render(&self, digits: &[u8]) -> String {
+        digits.iter().map(|digit| self.symbols.get(*digit as usize).copied().unwrap_or('?')).collect()
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::with_radix(10).unwrap()
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let symbols: Vec<char> = s.chars().collect();
+        if symbols.len() < 2 || symbols.len() > u8::MAX as usize {
+            return Err("Alphabet must have between 2 and 255 symbols");
+        }
+        if symbols.iter().any(|c| c.is_whitespace() || *c == '-' || *c == ',') {
+            return Err("Alphabet cannot contain whitespace, '-' or ','");
+        }
+        if symbols.iter().enumerate().any(|(i, c)| symbols[..i].contains(c)) {
+            return Err("Alphabet has a repeated symbol");
+        }
+        Ok(Alphabet{ symbols })
+    }
+}
+
+// a product id as its digit values, most significant first and without leading zeros,
+// so it is not capped at any integer width
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProductId {
+    digits: Vec<u8>
+}
+
+impl Ord for ProductId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.digits.len().cmp(&other.digits.len()).then_with(|| self.digits.cmp(&other.digits))
+    }
+}
+
+impl PartialOrd for ProductId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ProductId {
+    fn successor(&self, radix: u8) -> ProductId {
+        let mut digits = self.digits.clone();
+        for digit in digits.iter_mut().rev() {
+            if *digit + 1 < radix {
+                *digit += 1;
+                return ProductId{ digits };
             }
+            *digit = 0;
         }
-        true
+        digits.insert(0, 1);
+        ProductId{ digits }
+    }
+
+    fn to_biguint(&self, radix: u8) -> BigUint {
+        self.digits.iter().fold(BigUint::default(), |value, digit| value * radix + *digit)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 struct ProductIdRange {
-    lower_bound: String,
-    upper_bound: String
+    lower_bound: ProductId,
+    upper_bound: ProductId
+}
+
+impl ProductIdRange {
+    fn render(&self, alphabet: &Alphabet) -> String {
+        format!("{}-{}", alphabet.render(&self.lower_bound.digits), alphabet.render(&self.upper_bound.digits))
+    }
+
+    fn parse_with(s: &str, alphabet: &Alphabet) -> Result<ProductIdRange, &'static str> {
+        let (lower, upper) = s.trim().split_once('-').ok_or("Missing '-' separator")?;
+        if upper.contains('-') {
+            return Err("Too many '-' separators");
+        }
+
+        let lower_bound = parse_bound(lower, alphabet)?;
+        let upper_bound = parse_bound(upper, alphabet)?;
+        if lower_bound > upper_bound {
+            return Err("Lower bound is greater than upper bound");
+        }
+        Ok(ProductIdRange{ lower_bound, upper_bound })
+    }
 }
 
 impl Display for ProductIdRange {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}-{}", self.lower_bound, self.upper_bound)
+        write!(f, "{}", self.render(&Alphabet::with_radix(STANDARD_SYMBOLS.len()).unwrap()))
     }
 }
 
+fn parse_bound(s: &str, alphabet: &Alphabet) -> Result<ProductId, &'static str> {
+    if s.is_empty() {
+        return Err("Missing bound");
+    }
+    let digits = s.chars()
+        .map(|c| alphabet.digit_value(c))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or("Bound is not a number")?;
+    let first_significant = digits.iter().position(|digit| *digit != 0).unwrap_or(digits.len() - 1);
+    Ok(ProductId{ digits: digits[first_significant..].to_vec() })
+}
+
 impl FromStr for ProductIdRange {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lower_bound = s.split('-').next(); 
-        let upper_bound = s.split('-').next_back(); 
+        ProductIdRange::parse_with(s, &Alphabet::default())
+    }
+}
 
+fn invalid_ids_by<F>(range: &ProductIdRange, radix: u8, op: F) -> impl Iterator<Item = ProductId>
+where F: Fn(&[u8]) -> bool
+{
+    let upper_bound = range.upper_bound.clone();
+    std::iter::successors(Some(range.lower_bound.clone()), move |id| Some(id.successor(radix)))
+        .take_while(move |id| *id <= upper_bound)
+        .filter(move |id| !op(&id.digits))
+}
+
+fn repeat_block(block: &ProductId, times: usize) -> ProductId {
+    ProductId{ digits: block.digits.repeat(times) }
+}
 
-        match (lower_bound, upper_bound) {
-            (Some(l_value), Some(r_value)) => 
-                Ok(ProductIdRange{ lower_bound: l_value.to_string(), upper_bound: r_value.to_string() }),
-            _ =>
-                Err("Could nae parse")
+// builds every id in the range made of a repeated block, rather than testing each id.
+// ids of one length compare like their leading block, so the first block is the lower
+// bound's leading digits, or the one after if repeating it falls short, and blocks count
+// up from there until the repeated id passes the upper bound. ids are handed to `visit`
+// as they are built, in no particular order, and only for rules about repetition
+fn repeated_ids_in_range<F>(lower: &ProductId, upper: &ProductId, radix: u8, rule: &Rule, mut visit: F)
+where F: FnMut(ProductId)
+{
+    for length in lower.digits.len()..=upper.digits.len() {
+        let lower_for_length = if length == lower.digits.len() {
+            lower.clone()
+        } else {
+            ProductId{ digits: std::iter::once(1).chain(std::iter::repeat_n(0, length - 1)).collect() }
+        };
+        let upper_for_length = if length == upper.digits.len() {
+            upper.clone()
+        } else {
+            ProductId{ digits: vec![radix - 1; length] }
+        };
+
+        let periods = rule.periods(length as u32).unwrap_or_default();
+        for (i, period) in periods.iter().map(|period| *period as usize).enumerate() {
+            let times = length / period;
+            let leading_block = ProductId{ digits: lower_for_length.digits[..period].to_vec() };
+            let first_block = if repeat_block(&leading_block, times) >= lower_for_length {
+                leading_block
+            } else {
+                leading_block.successor(radix)
+            };
+
+            let blocks = std::iter::successors(Some(first_block), |block| Some(block.successor(radix)))
+                .take_while(|block| block.digits.len() == period);
+            for block in blocks {
+                let id = repeat_block(&block, times);
+                if id > upper_for_length {
+                    break;
+                }
+                // an id that also repeats with a shorter period was already built from it
+                if periods[..i].iter().any(|shorter| is_periodic(&id.digits, *shorter as usize)) {
+                    continue;
+                }
+                visit(id);
+            }
         }
     }
 }
 
-fn invalid_ids_by<F>(ids: Vec<ProductIdRange>, op: F) -> Result<Vec<u128>, String>
-where F: Fn(String) -> bool
+// ranges may be separated by commas, newlines or other whitespace in any mix, and
+// lines starting with '#' are comments
+fn parse_ranges(input: &str, alphabet: &Alphabet) -> Result<Vec<ProductIdRange>, String> {
+    let ranges: Vec<ProductIdRange> = input.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|s| !s.is_empty())
+        .map(|s| ProductIdRange::parse_with(s, alphabet).map_err(|e| format!("Invalid range '{}': {}", s, e)))
+        .collect::<Result<_, _>>()?;
+    if ranges.is_empty() {
+        return Err("No ranges in input".to_string());
+    }
+    Ok(ranges)
+}
+
+// what to do with ids shared by more than one range in the input
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OverlapPolicy {
+    Merge,
+    KeepDuplicates,
+}
+
+struct Options {
+    rule: Option<Rule>,
+    scan: bool,
+    explain: bool,
+    stats: bool,
+    overlaps: OverlapPolicy,
+    report_overlaps: bool,
+    alphabet: Alphabet,
+}
+
+impl Options {
+    fn from_args(args: &[String]) -> Result<Options, String> {
+        let mut options = Options { rule: None, scan: false, explain: false, stats: false, overlaps: OverlapPolicy::Merge, report_overlaps: false, alphabet: Alphabet::default() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rule" => {
+                    let expression = args.next().ok_or("Missing rule expression after --rule")?;
+                    options.rule = Some(Rule::from_str(expression).map_err(|e| format!("Invalid rule '{}': {}", expression, e))?);
+                }
+                "--radix" => {
+                    let radix = args.next().ok_or("Missing radix after --radix")?;
+                    let radix: usize = radix.parse().map_err(|_| format!("Invalid radix '{}'", radix))?;
+                    options.alphabet = Alphabet::with_radix(radix)?;
+                }
+                "--alphabet" => {
+                    let symbols = args.next().ok_or("Missing symbols after --alphabet")?;
+                    options.alphabet = Alphabet::from_str(symbols)?;
+                }
+                "--scan" => options.scan = true,
+                "--explain" => options.explain = true,
+                "--stats" => options.stats = true,
+                "--keep-duplicates" => options.overlaps = OverlapPolicy::KeepDuplicates,
+                "--report-overlaps" => options.report_overlaps = true,
+                _ => return Err(format!("Unexpected argument: {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+// sorts the ranges and merges any that overlap or touch, so each id appears once
+fn normalise_ranges(mut ranges: Vec<ProductIdRange>, radix: u8) -> Vec<ProductIdRange> {
+    ranges.sort_by(|a, b| (&a.lower_bound, &a.upper_bound).cmp(&(&b.lower_bound, &b.upper_bound)));
+
+    ranges.into_iter().fold(Vec::<ProductIdRange>::new(), |mut acc, range| {
+        match acc.last_mut() {
+            Some(last) if range.lower_bound <= last.upper_bound.successor(radix) => {
+                if range.upper_bound > last.upper_bound {
+                    last.upper_bound = range.upper_bound;
+                }
+            }
+            _ => acc.push(range),
+        }
+        acc
+    })
+}
+
+// every pair of ranges sharing at least one id, in order of their lower bounds
+fn find_overlaps(ranges: &[ProductIdRange]) -> Vec<(ProductIdRange, ProductIdRange)> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_by(|a, b| (&a.lower_bound, &a.upper_bound).cmp(&(&b.lower_bound, &b.upper_bound)));
+
+    let mut overlaps = vec![];
+    for (i, range) in sorted.iter().enumerate() {
+        for other in sorted[i + 1..].iter() {
+            if other.lower_bound > range.upper_bound {
+                break;
+            }
+            overlaps.push((range.clone(), other.clone()));
+        }
+    }
+    overlaps
+}
+
+fn ranges_for(input: &str, options: &Options) -> Result<Vec<ProductIdRange>, String> {
+    let parsed_ranges = parse_ranges(input, &options.alphabet)?;
+    Ok(match options.overlaps {
+        OverlapPolicy::Merge => normalise_ranges(parsed_ranges, options.alphabet.radix()),
+        OverlapPolicy::KeepDuplicates => parsed_ranges,
+    })
+}
+
+// the invalid ids in a range under a rule, either generating them or scanning every id,
+// which is slow but useful to cross-check the generator on small inputs
+fn for_each_invalid_id<F>(range: &ProductIdRange, rule: &Rule, options: &Options, visit: F)
+where F: FnMut(ProductId)
 {
-    Ok(ids.iter().flat_map(|range| {
-        let lower = range.lower_bound.parse::<u128>()
-            .map_err(|_| "Not valid numbers in bounds".to_string()).ok()?;
-        let upper = range.upper_bound.parse::<u128>()
-            .map_err(|_| "Not valid numbers in bounds".to_string()).ok()?;
-        
+    let radix = options.alphabet.radix();
+    if options.scan || !rule.is_repetition() {
+        invalid_ids_by(range, radix, |id| rule.is_valid(id)).for_each(visit);
+    } else {
+        repeated_ids_in_range(&range.lower_bound, &range.upper_bound, radix, rule, visit);
+    }
+}
+
+fn invalids_in(range: &ProductIdRange, rule: &Rule, options: &Options) -> Vec<ProductId> {
+    let mut invalids = vec![];
+    for_each_invalid_id(range, rule, options, |id| invalids.push(id));
+    invalids.sort();
+    invalids
+}
+
+// running totals over invalid ids, so ranges can be summarised without listing them
+#[derive(Debug, Clone, PartialEq, Default)]
+struct InvalidIdStats {
+    count: usize,
+    sum: BigUint,
+    min: Option<ProductId>,
+    max: Option<ProductId>
+}
+
+impl InvalidIdStats {
+    fn add(&mut self, id: ProductId, radix: u8) {
+        self.count += 1;
+        self.sum += &id.to_biguint(radix);
+        if self.min.as_ref().is_none_or(|min| id < *min) {
+            self.min = Some(id.clone());
+        }
+        if self.max.as_ref().is_none_or(|max| id > *max) {
+            self.max = Some(id);
+        }
+    }
 
-            let invalids: Vec<u128> = (lower..=upper).filter(|num| {
-                !op(num.to_string())
-            }).collect();
-            Some(invalids)
-        
-    }).flatten().collect())
+    fn merge(&mut self, other: &InvalidIdStats) {
+        self.count += other.count;
+        self.sum += &other.sum;
+        if let Some(other_min) = &other.min && self.min.as_ref().is_none_or(|min| other_min < min) {
+            self.min = Some(other_min.clone());
+        }
+        if let Some(other_max) = &other.max && self.max.as_ref().is_none_or(|max| other_max > max) {
+            self.max = Some(other_max.clone());
+        }
+    }
+
+    fn render(&self, alphabet: &Alphabet) -> String {
+        let render_id = |id: &Option<ProductId>| id.as_ref().map_or("-".to_string(), |id| alphabet.render(&id.digits));
+        format!("count {} sum {} min {} max {}", self.count, self.sum, render_id(&self.min), render_id(&self.max))
+    }
 }
 
-fn part_one() -> Result<(), String> {
-    let lines: Vec<&str> = INPUT.lines().collect();
-    let unparsed_input = lines.first().unwrap();
-    let parsed_ranges: Vec<ProductIdRange> = unparsed_input.split(",").map(ProductIdRange::from_str).collect::<Result<_, _>>()?;
+type RangeStats = (ProductIdRange, InvalidIdStats);
 
-    let invalids = invalid_ids_by(parsed_ranges, |s| s.is_valid_product_id_by_one_repetition())?;
+// stats per range, and over every range. ranges come in the order ranges_for gives them,
+// which under the default merge policy is sorted with overlaps merged
+fn aggregate_invalids(input: &str, rule: &Rule, options: &Options) -> Result<(Vec<RangeStats>, InvalidIdStats), String> {
+    let radix = options.alphabet.radix();
+    let per_range: Vec<RangeStats> = ranges_for(input, options)?.into_iter()
+        .map(|range| {
+            let mut stats = InvalidIdStats::default();
+            for_each_invalid_id(&range, rule, options, |id| stats.add(id, radix));
+            (range, stats)
+        })
+        .collect();
 
-    let sum_of_invalids: u128 = invalids
-        .iter()
-        .sum();
+    let mut overall = InvalidIdStats::default();
+    per_range.iter().for_each(|(_, stats)| overall.merge(stats));
+    Ok((per_range, overall))
+}
 
-    println!("Sum of invalids: {}", sum_of_invalids);
-    
-    Ok(())
+fn sum_of_invalids(input: &str, rule: &Rule, options: &Options) -> Result<BigUint, String> {
+    let (_, overall) = aggregate_invalids(input, rule, options)?;
+    Ok(overall.sum)
 }
 
-fn part_two() -> Result<(), String> {
-    let lines: Vec<&str> = INPUT.lines().collect();
-    let unparsed_input = lines.first().unwrap();
-    let parsed_ranges: Vec<ProductIdRange> = unparsed_input.split(",").map(ProductIdRange::from_str).collect::<Result<_, _>>()?;
+// lists every invalid id per range with the block that repeats in it, then counts by block length
+fn explain(input: &str, rule: &Rule, options: &Options) -> Result<String, String> {
+    let alphabet = &options.alphabet;
+    let mut explanation = String::new();
+    let mut by_block_length: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut without_block = 0;
 
-    let invalids = invalid_ids_by(parsed_ranges, |s| s.is_valid_product_id_by_many_repetitions())?;
+    for range in ranges_for(input, options)? {
+        let invalids = invalids_in(&range, rule, options);
+        writeln!(explanation, "Range {}: {} invalid", range.render(alphabet), invalids.len()).map_err(|e| e.to_string())?;
+        for id in invalids {
+            match rule.repeating_block(&id.digits) {
+                Some((block, times)) => {
+                    *by_block_length.entry(block.len()).or_insert(0) += 1;
+                    writeln!(explanation, "  {} = \"{}\" x {}", alphabet.render(&id.digits), alphabet.render(block), times)
+                }
+                None => {
+                    without_block += 1;
+                    writeln!(explanation, "  {} (no repeating block)", alphabet.render(&id.digits))
+                }
+            }.map_err(|e| e.to_string())?;
+        }
+    }
 
-    let sum_of_invalids: u128 = invalids
-        .iter()
-        .sum();
+    writeln!(explanation, "Invalid ids by block length:").map_err(|e| e.to_string())?;
+    for (length, count) in by_block_length {
+        writeln!(explanation, "  {}: {}", length, count).map_err(|e| e.to_string())?;
+    }
+    if without_block > 0 {
+        writeln!(explanation, "  none: {}", without_block).map_err(|e| e.to_string())?;
+    }
+    Ok(explanation)
+}
 
+fn run_rule(input: &str, rule: &Rule, options: &Options) -> Result<(), String> {
+    if options.explain {
+        print!("{}", explain(input, rule, options)?);
+    }
+    if options.stats {
+        let (per_range, overall) = aggregate_invalids(input, rule, options)?;
+        for (range, stats) in &per_range {
+            println!("{}: {}", range.render(&options.alphabet), stats.render(&options.alphabet));
+        }
+        println!("Overall: {}", overall.render(&options.alphabet));
+        println!("Sum of invalids: {}", overall.sum);
+        return Ok(());
+    }
+    let sum_of_invalids = sum_of_invalids(input, rule, options)?;
     println!("Sum of invalids: {}", sum_of_invalids);
-    
     Ok(())
 }
 
-fn main(){
-    let _ = part_one();
-    let _ = part_two();
+fn report_overlaps(input: &str, options: &Options) -> Result<(), String> {
+    let alphabet = &options.alphabet;
+    let overlaps = find_overlaps(&parse_ranges(input, alphabet)?);
+    println!("Overlapping ranges: {}", overlaps.len());
+    overlaps.iter().for_each(|(first, second)| println!("{} overlaps {}", first.render(alphabet), second.render(alphabet)));
+    Ok(())
+}
+
+fn part_one(input: &str, options: &Options) -> Result<(), String> {
+    run_rule(input, &Rule::from_str(PART_ONE_RULE)?, options)
+}
+
+fn part_two(input: &str, options: &Options) -> Result<(), String> {
+    run_rule(input, &Rule::from_str(PART_TWO_RULE)?, options)
+}
+
+fn main() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = Options::from_args(&args)?;
+    if options.report_overlaps {
+        report_overlaps(INPUT, &options)?;
+    }
+    if let Some(rule) = &options.rule {
+        return run_rule(INPUT, rule, &options);
+    }
+    part_one(INPUT, &options)?;
+    part_two(INPUT, &options)
 }
 
 #[cfg(test)]
@@ -131,17 +669,57 @@ mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
 
+    fn id(value: u128) -> ProductId {
+        ProductId{ digits: value.to_string().bytes().map(|b| b - b'0').collect() }
+    }
+
     #[test]
     fn test_parses_range() {
         // Given
         let input: &str = "1188511880-1188511890";
-        let expected =  Ok(ProductIdRange{ lower_bound: "1188511880".to_owned(), upper_bound: "1188511890".to_owned() });
+        let expected =  Ok(ProductIdRange{ lower_bound: id(1188511880), upper_bound: id(1188511890) });
         // When
         let actual = ProductIdRange::from_str(input);
         // Then
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_parses_range_with_surrounding_whitespace() {
+        // Given
+        let test_cases = vec![" 11-22", "11-22\n", "\t11-22\r\n"];
+
+        for input in test_cases {
+            // When
+            let actual = ProductIdRange::from_str(input);
+            // Then
+            assert_eq!(actual, Ok(ProductIdRange{ lower_bound: id(11), upper_bound: id(22) }), "Failed for input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_range_rejects_malformed_input() {
+        // Given
+        let test_cases = vec![
+            ("1-2-3", "Too many '-' separators"),
+            ("abc", "Missing '-' separator"),
+            ("5-", "Missing bound"),
+            ("-5", "Missing bound"),
+            ("+5-10", "Bound is not a number"),
+            ("5-1O", "Bound is not a number"),
+            ("5 - 10", "Bound is not a number"),
+            ("90-10", "Lower bound is greater than upper bound"),
+            ("1000-0999", "Lower bound is greater than upper bound"),
+        ];
+
+        for (input, expected) in test_cases {
+            // When
+            let actual = ProductIdRange::from_str(input);
+            // Then
+            assert_eq!(actual, Err(expected), "Failed for input: {}", input);
+        }
+    }
+
     #[test]
     fn test_product_id_is_valid() {
         // Given
@@ -155,7 +733,7 @@ mod tests {
 
         for (input, expected) in test_cases {
             // When
-            let actual = input.to_string().is_valid_product_id_by_one_repetition();
+            let actual = Rule::from_str(PART_ONE_RULE).unwrap().is_valid(input.as_bytes());
             // Then 
             assert_eq!(actual, expected, "Failed for input: {}", input);
         }
@@ -177,9 +755,457 @@ mod tests {
 
         for (input, expected) in test_cases {
             // When
-            let actual = input.to_string().is_valid_product_id_by_many_repetitions();
+            let actual = Rule::from_str(PART_TWO_RULE).unwrap().is_valid(input.as_bytes());
             // Then 
             assert_eq!(actual, expected, "Failed for input: {}", input);
         }
     }
-}
\ No newline at end of file
+
+    static EXAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
+
+    #[test]
+    fn test_sum_of_invalids_for_example() {
+        // Given
+        let options = Options::from_args(&[]).unwrap();
+        let part_one_rule = Rule::from_str(PART_ONE_RULE).unwrap();
+        let part_two_rule = Rule::from_str(PART_TWO_RULE).unwrap();
+
+        // When
+        let part_one = sum_of_invalids(EXAMPLE, &part_one_rule, &options);
+        let part_two = sum_of_invalids(EXAMPLE, &part_two_rule, &options);
+
+        // Then
+        assert_eq!(part_one, Ok(BigUint::from(1227775554u64)));
+        assert_eq!(part_two, Ok(BigUint::from(4174379265u64)));
+    }
+
+    #[test]
+    fn test_repeated_ids_in_range_matches_scanning() {
+        // Given
+        let test_cases = vec![(1, 10_000), (95, 115), (998, 1012), (100_000, 300_000), (1_000_000, 1_200_000)];
+        let rules = vec![PART_ONE_RULE, PART_TWO_RULE, "repeats(3)", "block_at_least(2)", "repeats(2) | repeats(3)"];
+
+        for (lower, upper) in test_cases {
+            for expression in &rules {
+                let rule = Rule::from_str(expression).unwrap();
+                // When
+                let mut generated = vec![];
+                repeated_ids_in_range(&id(lower), &id(upper), 10, &rule, |id| generated.push(id));
+                generated.sort();
+                let scanned: Vec<ProductId> = (lower..=upper).map(id).filter(|id| rule.is_invalid(&id.digits)).collect();
+                // Then
+                assert_eq!(generated, scanned, "Failed for {}-{} with {}", lower, upper, expression);
+            }
+        }
+    }
+
+    #[test]
+    fn test_repeated_ids_in_range_handles_wide_ranges() {
+        // Given
+        let lower = 1;
+        let upper = 10u128.pow(12);
+
+        // When
+        let mut count = 0;
+        let mut largest = id(0);
+        repeated_ids_in_range(&id(lower), &id(upper), 10, &Rule::Repeats(2), |id| {
+            count += 1;
+            largest = largest.clone().max(id);
+        });
+
+        // Then - 9 * 10^(n-1) blocks for each half length n up to 6 digits
+        assert_eq!(count, 999_999);
+        assert_eq!(largest, id(999999999999));
+    }
+
+    #[test]
+    fn test_sum_of_invalids_beyond_u128() {
+        // Given - 42 digit ids, repeated blocks of 21 digits
+        let block = "123456789012345678901";
+        let input = format!("{}{}-{}{}", block, "0".repeat(21), block, "9".repeat(21));
+        let options = Options::from_args(&[]).unwrap();
+
+        // When
+        let actual = sum_of_invalids(&input, &Rule::Repeats(2), &options);
+
+        // Then
+        assert_eq!(actual.map(|sum| sum.to_string()), Ok(format!("{}{}", block, block)));
+    }
+
+    #[test]
+    fn test_sum_of_invalids_in_hex() {
+        // Given - 11 to ff in hex, 0x11 * (1 + 2 + ... + 15)
+        let options = Options::from_args(&["--radix".to_string(), "16".to_string()]).unwrap();
+
+        // When
+        let actual = sum_of_invalids("a-FF", &Rule::Repeats(2), &options);
+
+        // Then
+        assert_eq!(actual, Ok(BigUint::from(2040u64)));
+    }
+
+    #[test]
+    fn test_explain_with_custom_alphabet() {
+        // Given - a base 3 alphabet where x = 0, y = 1, z = 2
+        let options = Options::from_args(&["--alphabet".to_string(), "xyz".to_string()]).unwrap();
+
+        // When
+        let actual = explain("yxxx-zzzz", &Rule::Repeats(2), &options);
+
+        // Then
+        assert_eq!(actual, Ok([
+            "Range yxxx-zzzz: 6 invalid",
+            "  yxyx = \"yx\" x 2",
+            "  yyyy = \"yy\" x 2",
+            "  yzyz = \"yz\" x 2",
+            "  zxzx = \"zx\" x 2",
+            "  zyzy = \"zy\" x 2",
+            "  zzzz = \"zz\" x 2",
+            "Invalid ids by block length:",
+            "  2: 6",
+            "",
+        ].join("\n")));
+    }
+
+    #[test]
+    fn test_alphabet_rejects_bad_symbols() {
+        // Given
+        let test_cases = vec![
+            ("a", "Alphabet must have between 2 and 255 symbols"),
+            ("ab-", "Alphabet cannot contain whitespace, '-' or ','"),
+            ("aba", "Alphabet has a repeated symbol"),
+        ];
+
+        for (input, expected) in test_cases {
+            // When
+            let actual = Alphabet::from_str(input);
+            // Then
+            assert_eq!(actual, Err(expected), "Failed for input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_biguint_display_and_add() {
+        // Given
+        let test_cases = vec![
+            (0, "0"),
+            (7, "14"),
+            (1_000_000_000, "2000000000"),
+            (u128::MAX, "680564733841876926926749214863536422910"),
+        ];
+
+        for (value, expected_doubled) in test_cases {
+            // When
+            let mut doubled = BigUint::from(value);
+            doubled += &BigUint::from(value);
+            // Then
+            assert_eq!(BigUint::from(value).to_string(), value.to_string());
+            assert_eq!(doubled.to_string(), expected_doubled);
+        }
+    }
+
+    fn range(lower_bound: u128, upper_bound: u128) -> ProductIdRange {
+        ProductIdRange{ lower_bound: id(lower_bound), upper_bound: id(upper_bound) }
+    }
+
+    #[test]
+    fn test_normalise_ranges_merges_overlapping_and_touching() {
+        // Given
+        let input = vec![range(95, 115), range(10, 30), range(20, 40), range(41, 50), range(100, 110)];
+
+        // When
+        let actual = normalise_ranges(input, 10);
+
+        // Then
+        assert_eq!(actual, vec![range(10, 50), range(95, 115)]);
+    }
+
+    #[test]
+    fn test_find_overlaps() {
+        // Given
+        let input = vec![range(95, 115), range(10, 30), range(20, 40), range(41, 50), range(100, 110)];
+
+        // When
+        let actual = find_overlaps(&input);
+
+        // Then
+        assert_eq!(actual, vec![
+            (range(10, 30), range(20, 40)),
+            (range(95, 115), range(100, 110)),
+        ]);
+    }
+
+    #[test]
+    fn test_sum_of_invalids_errors_on_rejected_range() {
+        // Given
+        let options = Options::from_args(&[]).unwrap();
+
+        // When
+        let actual = sum_of_invalids("11-22,90-10", &Rule::Repeats(2), &options);
+
+        // Then
+        assert_eq!(actual, Err("Invalid range '90-10': Lower bound is greater than upper bound".to_string()));
+    }
+
+    #[test]
+    fn test_sum_of_invalids_by_overlap_policy() {
+        // Given - 11 and 22 are in both ranges
+        let input = "10-25,11-22";
+        let merge = Options::from_args(&[]).unwrap();
+        let keep = Options::from_args(&["--keep-duplicates".to_string()]).unwrap();
+
+        // When
+        let merged = sum_of_invalids(input, &Rule::Repeats(2), &merge);
+        let kept = sum_of_invalids(input, &Rule::Repeats(2), &keep);
+
+        // Then
+        assert_eq!(merged, Ok(BigUint::from(33u64)));
+        assert_eq!(kept, Ok(BigUint::from(66u64)));
+    }
+
+    #[test]
+    fn test_rule_from_str() {
+        // Given
+        let test_cases = vec![
+            ("repeats(2)", Rule::Repeats(2)),
+            ("palindrome & !run(3)", Rule::And(
+                Box::new(Rule::Palindrome),
+                Box::new(Rule::Not(Box::new(Rule::Run(3)))),
+            )),
+            ("repeats(3) | block_at_least(2) & palindrome", Rule::Or(
+                Box::new(Rule::Repeats(3)),
+                Box::new(Rule::And(Box::new(Rule::BlockAtLeast(2)), Box::new(Rule::Palindrome))),
+            )),
+            (" ( repeats_at_least( 2 ) | palindrome ) & run(2)", Rule::And(
+                Box::new(Rule::Or(Box::new(Rule::RepeatsAtLeast(2)), Box::new(Rule::Palindrome))),
+                Box::new(Rule::Run(2)),
+            )),
+        ];
+
+        for (input, expected) in test_cases {
+            // When
+            let actual = Rule::from_str(input);
+            // Then
+            assert_eq!(actual, Ok(expected), "Failed for input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_rule_from_str_rejects_malformed_expressions() {
+        // Given
+        let test_cases = vec![
+            ("", "Expected a rule"),
+            ("twice", "Unknown rule"),
+            ("repeats", "Missing '(' after rule name"),
+            ("repeats(x)", "Rule argument is not a number"),
+            ("repeats(1)", "Rule argument is too small"),
+            ("(palindrome", "Missing ')'"),
+            ("palindrome run(2)", "Unexpected trailing characters in rule"),
+        ];
+
+        for (input, expected) in test_cases {
+            // When
+            let actual = Rule::from_str(input);
+            // Then
+            assert_eq!(actual, Err(expected), "Failed for input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_rules_flag_invalid_ids() {
+        // Given
+        let test_cases = vec![
+            ("repeats(3)", "123123123", true),
+            ("repeats(3)", "111111", true),
+            ("repeats(3)", "123123", false),
+            ("block_at_least(3)", "121212", false),
+            ("block_at_least(3)", "123123", true),
+            ("palindrome", "12321", true),
+            ("palindrome", "1232", false),
+            ("run(3)", "1222", true),
+            ("run(3)", "1221", false),
+            ("repeats_at_least(2) & !palindrome", "1212", true),
+            ("repeats_at_least(2) & !palindrome", "1111", false),
+        ];
+
+        for (expression, id, expected) in test_cases {
+            // When
+            let actual = Rule::from_str(expression).unwrap().is_invalid(id.as_bytes());
+            // Then
+            assert_eq!(actual, expected, "Failed for {} with {}", id, expression);
+        }
+    }
+
+    #[test]
+    fn test_sum_of_invalids_scans_rules_not_about_repetition() {
+        // Given
+        let options = Options::from_args(&["--rule".to_string(), "palindrome & run(2)".to_string()]).unwrap();
+
+        // When
+        let actual = sum_of_invalids("100-130", options.rule.as_ref().unwrap(), &options);
+
+        // Then - 111 and 121 are palindromes, only 111 has a run
+        assert_eq!(actual, Ok(BigUint::from(111u64)));
+    }
+
+    #[test]
+    fn test_repeating_block() {
+        // Given
+        let test_cases = vec![
+            (PART_TWO_RULE, "123123123", Some(("123".as_bytes(), 3))),
+            (PART_TWO_RULE, "1111", Some(("1".as_bytes(), 4))),
+            (PART_ONE_RULE, "1111", Some(("11".as_bytes(), 2))),
+            ("palindrome", "12321", None),
+        ];
+
+        for (expression, id, expected) in test_cases {
+            // When
+            let actual = Rule::from_str(expression).unwrap().repeating_block(id.as_bytes());
+            // Then
+            assert_eq!(actual, expected, "Failed for {} with {}", id, expression);
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        // Given
+        let options = Options::from_args(&[]).unwrap();
+        let rule = Rule::from_str("repeats_at_least(2) | palindrome").unwrap();
+
+        // When
+        let actual = explain("95-115,1010-1012,123123120-123123125", &rule, &options);
+
+        // Then
+        assert_eq!(actual, Ok([
+            "Range 95-115: 3 invalid",
+            "  99 = \"9\" x 2",
+            "  101 (no repeating block)",
+            "  111 = \"1\" x 3",
+            "Range 1010-1012: 1 invalid",
+            "  1010 = \"10\" x 2",
+            "Range 123123120-123123125: 1 invalid",
+            "  123123123 = \"123\" x 3",
+            "Invalid ids by block length:",
+            "  1: 2",
+            "  2: 1",
+            "  3: 1",
+            "  none: 1",
+            "",
+        ].join("\n")));
+    }
+
+    #[test]
+    fn test_aggregate_invalids_per_range_and_overall() {
+        // Given
+        let options = Options::from_args(&[]).unwrap();
+        let rule = Rule::from_str(PART_TWO_RULE).unwrap();
+
+        // When
+        let (per_range, overall) = aggregate_invalids("95-115,200-210,998-1012", &rule, &options).unwrap();
+
+        // Then
+        assert_eq!(per_range, vec![
+            (range(95, 115), InvalidIdStats{ count: 2, sum: BigUint::from(210u64), min: Some(id(99)), max: Some(id(111)) }),
+            (range(200, 210), InvalidIdStats::default()),
+            (range(998, 1012), InvalidIdStats{ count: 2, sum: BigUint::from(2009u64), min: Some(id(999)), max: Some(id(1010)) }),
+        ]);
+        assert_eq!(overall, InvalidIdStats{ count: 4, sum: BigUint::from(2219u64), min: Some(id(99)), max: Some(id(1010)) });
+    }
+
+    #[test]
+    fn test_aggregate_invalids_matches_scanning() {
+        // Given
+        let generating = Options::from_args(&[]).unwrap();
+        let scanning = Options::from_args(&["--scan".to_string()]).unwrap();
+        let rule = Rule::from_str(PART_TWO_RULE).unwrap();
+
+        // When
+        let generated = aggregate_invalids(EXAMPLE, &rule, &generating);
+        let scanned = aggregate_invalids(EXAMPLE, &rule, &scanning);
+
+        // Then
+        assert_eq!(generated, scanned);
+    }
+
+    #[test]
+    fn test_parse_ranges_with_mixed_separators_and_comments() {
+        // Given
+        let input = "# first batch\n11-22,95-115,\n  998-1012 222220-222224\r\n\t# second batch\n1698522-1698528 ,\n\n";
+
+        // When
+        let actual = parse_ranges(input, &Alphabet::default());
+
+        // Then
+        assert_eq!(actual, Ok(vec![
+            range(11, 22),
+            range(95, 115),
+            range(998, 1012),
+            range(222220, 222224),
+            range(1698522, 1698528),
+        ]));
+    }
+
+    #[test]
+    fn test_parse_ranges_errors_on_empty_input() {
+        // Given
+        let test_cases = vec!["", "\n\n", " , ,\n", "# only a comment\n"];
+
+        for input in test_cases {
+            // When
+            let actual = parse_ranges(input, &Alphabet::default());
+            // Then
+            assert_eq!(actual, Err("No ranges in input".to_string()), "Failed for input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parts_reject_empty_input() {
+        for args in [vec![], vec!["--stats".to_string()]] {
+            // Given
+            let options = Options::from_args(&args).unwrap();
+            // When
+            let part_one = part_one("", &options);
+            let part_two = part_two("# no ranges yet\n", &options);
+            // Then
+            assert_eq!(part_one, Err("No ranges in input".to_string()), "Failed for {:?}", args);
+            assert_eq!(part_two, Err("No ranges in input".to_string()), "Failed for {:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_report_overlaps_reads_the_given_input() {
+        // Given
+        let options = Options::from_args(&["--report-overlaps".to_string()]).unwrap();
+
+        // When
+        let overlapping = report_overlaps("11-22,20-30", &options);
+        let empty = report_overlaps("", &options);
+
+        // Then
+        assert_eq!(overlapping, Ok(()));
+        assert_eq!(empty, Err("No ranges in input".to_string()));
+    }
+
+    #[test]
+    fn test_rule_arguments_at_the_u32_bound_are_accepted() {
+        // Given
+        let options = Options::from_args(&[]).unwrap();
+        let rule = Rule::from_str("repeats(4294967295)").unwrap();
+
+        // When
+        let actual = sum_of_invalids("1000-9999", &rule, &options);
+
+        // Then
+        assert_eq!(actual, Ok(BigUint::from(0u64)));
+    }
+
+    #[test]
+    fn test_rule_arguments_beyond_u32_are_rejected() {
+        for rule in ["repeats(4294967296)", "block_at_least(99999999999999999999999)"] {
+            // When
+            let actual = Rule::from_str(rule);
+            // Then
+            assert_eq!(actual, Err("Rule argument is too large"), "Failed for {}", rule);
+        }
+    }
+}